@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use rand::prelude::SliceRandom;
+use rand::rngs::StdRng;
+use crate::game::{Game,GameState};
+
+/// An evaluator's estimate for a leaf of the search.
+pub struct Evaluation<A> {
+    pub values: HashMap<i32, f64>, // {player: value estimate}, in [-1, 1] unless the game's utilities aren't
+    pub prior: Option<Vec<(A, f64)>>, // P(s,a) over the legal actions, if the evaluator has one
+    /// (player, action) for every move a rollout played from the leaf, for RAVE. Only filled in
+    /// by `Evaluator::evaluate_recording_moves`.
+    pub moves: Vec<(i32, A)>
+}
+
+impl<A> Evaluation<A> {
+    pub fn new(values: HashMap<i32, f64>, prior: Option<Vec<(A, f64)>>) -> Evaluation<A> {
+        Evaluation { values, prior, moves: Vec::new() }
+    }
+
+    /// The exact values of a terminal state, its `GameState::utilities`.
+    pub fn terminal<S: GameState>(state: &S) -> Evaluation<A> {
+        let values = state.utilities().expect("No result for terminal state?").into_iter().collect();
+        Evaluation::new(values, None)
+    }
+}
+
+/// Estimates the value of the leaves `MCTS` reaches.
+pub trait Evaluator<G: Game> {
+    fn evaluate(&mut self, game: &mut G, state: Rc<G::State>, rng: &mut StdRng) -> Evaluation<G::Action>;
+
+    /// `evaluate`, also recording the moves a rollout played in `Evaluation::moves`. `MCTS` only
+    /// asks for them when RAVE is on, evaluators that don't play moves can keep the default.
+    fn evaluate_recording_moves(&mut self, game: &mut G, state: Rc<G::State>, rng: &mut StdRng) -> Evaluation<G::Action> {
+        self.evaluate(game, state, rng)
+    }
+
+    /// Prior for a state that is being expanded without having been evaluated, e.g. the root.
+    /// Evaluators that never produce a prior should override this to skip the evaluation.
+    fn prior(&mut self, game: &mut G, state: Rc<G::State>, rng: &mut StdRng) -> Option<Vec<(G::Action, f64)>> {
+        self.evaluate(game, state, rng).prior
+    }
+}
+
+/// Plays uniformly random moves until the game ends.
+#[derive(Debug, Clone, Copy)]
+pub struct RandomRollout;
+
+impl<G: Game> Evaluator<G> for RandomRollout {
+    fn evaluate(&mut self, game: &mut G, state: Rc<G::State>, rng: &mut StdRng) -> Evaluation<G::Action> {
+        Evaluation::terminal(&*random_moves(game, state, usize::MAX, rng, None))
+    }
+
+    fn evaluate_recording_moves(&mut self, game: &mut G, state: Rc<G::State>, rng: &mut StdRng) -> Evaluation<G::Action> {
+        let mut moves = Vec::new();
+        let end = random_moves(game, state, usize::MAX, rng, Some(&mut moves));
+        Evaluation { moves, ..Evaluation::terminal(&*end) }
+    }
+
+    fn prior(&mut self, _game: &mut G, _state: Rc<G::State>, _rng: &mut StdRng) -> Option<Vec<(G::Action, f64)>> {
+        None
+    }
+}
+
+/// Plays at most `max_depth` random moves and hands the state it stopped on to `cutoff`
+/// if the game hasn't ended by then.
+#[derive(Debug, Clone)]
+pub struct TruncatedRollout<E> {
+    pub max_depth: usize,
+    pub cutoff: E
+}
+
+impl<E> TruncatedRollout<E> {
+    /// `evaluate`, recording the moves played, the cutoff's included, if `record_moves` is set.
+    fn rollout<G: Game>(&mut self, game: &mut G, state: Rc<G::State>, rng: &mut StdRng, record_moves: bool) -> Evaluation<G::Action>
+    where
+        E: Evaluator<G>
+    {
+        let mut moves = Vec::new();
+        let cur_state = random_moves(game, state, self.max_depth, rng, record_moves.then_some(&mut moves));
+        if *cur_state.is_terminal() {
+            return Evaluation { moves, ..Evaluation::terminal(&*cur_state) };
+        }
+        let mut evaluation = if record_moves {
+            self.cutoff.evaluate_recording_moves(game, cur_state, rng)
+        } else {
+            self.cutoff.evaluate(game, cur_state, rng)
+        };
+        evaluation.prior = None; // the prior was for the state we stopped on, not the leaf
+        moves.append(&mut evaluation.moves);
+        evaluation.moves = moves;
+        evaluation
+    }
+}
+
+impl<G: Game, E: Evaluator<G>> Evaluator<G> for TruncatedRollout<E> {
+    fn evaluate(&mut self, game: &mut G, state: Rc<G::State>, rng: &mut StdRng) -> Evaluation<G::Action> {
+        self.rollout(game, state, rng, false)
+    }
+
+    fn evaluate_recording_moves(&mut self, game: &mut G, state: Rc<G::State>, rng: &mut StdRng) -> Evaluation<G::Action> {
+        self.rollout(game, state, rng, true)
+    }
+
+    fn prior(&mut self, _game: &mut G, _state: Rc<G::State>, _rng: &mut StdRng) -> Option<Vec<(G::Action, f64)>> {
+        None
+    }
+}
+
+/// Scores non-terminal states with a static evaluation function, `{player: value}`.
+#[derive(Clone)]
+pub struct HeuristicEvaluator<F>(pub F);
+
+impl<G: Game, F: FnMut(&G::State) -> HashMap<i32, f64>> Evaluator<G> for HeuristicEvaluator<F> {
+    fn evaluate(&mut self, _game: &mut G, state: Rc<G::State>, _rng: &mut StdRng) -> Evaluation<G::Action> {
+        if *state.is_terminal() {
+            return Evaluation::terminal(&*state);
+        }
+        Evaluation::new((self.0)(&state), None)
+    }
+
+    fn prior(&mut self, _game: &mut G, _state: Rc<G::State>, _rng: &mut StdRng) -> Option<Vec<(G::Action, f64)>> {
+        None
+    }
+}
+
+/// Plays up to `max_depth` random moves from `state` and returns the state it stopped on. Every
+/// player's move is added to `moves` if given, chance outcomes aren't anyone's move.
+fn random_moves<G: Game>(
+    game: &mut G,
+    state: Rc<G::State>,
+    max_depth: usize,
+    rng: &mut StdRng,
+    mut moves: Option<&mut Vec<(i32, G::Action)>>
+) -> Rc<G::State> {
+    let mut cur_state = state;
+    for _ in 0..max_depth {
+        if *cur_state.is_terminal() {
+            break;
+        }
+        let action = random_action(&*cur_state, rng);
+        if let Some(moves) = moves.as_mut().filter(|_| cur_state.chance_outcomes().is_none()) {
+            moves.push((*cur_state.player(), action.clone()));
+        }
+        cur_state = game.transition(cur_state, action);
+    }
+    cur_state
+}
+
+/// A uniformly random legal action, or an outcome drawn by its probability at a chance state.
+pub(crate) fn random_action<S: GameState>(state: &S, rng: &mut StdRng) -> S::Action {
+    match state.chance_outcomes() {
+        Some(outcomes) => outcomes.choose_weighted(rng, |(_, p)| *p).expect("Bad chance outcomes").0.clone(),
+        None => state.all_legal_actions().as_ref().unwrap().choose(rng).unwrap().clone()
+    }
+}
@@ -1,17 +1,79 @@
 use std::rc::Rc;
-use ndarray::Array2;
+use ndarray::{Array2,Array3};
+use rand::rngs::StdRng;
+use std::fmt::Debug;
 use std::hash::Hash;
 
 pub trait GameState: PartialEq + Eq + Hash {
-    fn state(&self) -> &Array2<i8>;
+    type Action: Clone + Eq + Hash + Debug;
+    type Board: Clone + Eq + Hash + Debug; // whatever representation suits the game: a grid, bitboards, piece lists...
+    fn state(&self) -> &Self::Board;
     fn is_terminal(&self) -> &bool;
     fn player(&self) -> &i32;
     fn result(&self) -> &Option<Vec<(i32,i32)>>;
-    fn all_legal_actions(&self) -> &Option<Vec<(usize, usize)>>;
+    fn all_legal_actions(&self) -> &Option<Vec<Self::Action>>;
+
+    /// `Some` outcomes with their probabilities if the state is waiting on a random event, like a
+    /// die roll, instead of a player. Outcomes are applied with `Game::transition` like actions.
+    fn chance_outcomes(&self) -> &Option<Vec<(Self::Action, f64)>> {
+        &None
+    }
+
+    /// `(player, utility)` at a terminal state, for payoffs that aren't whole numbers, like a score
+    /// margin. Defaults to `result`.
+    fn utilities(&self) -> Option<Vec<(i32, f64)>> {
+        self.result().as_ref().map(|result| result.iter().map(|&(player, reward)| (player, reward as f64)).collect())
+    }
 }
 
 pub trait Game {
-    type State: GameState;
-    fn get_state(&mut self, board: &Array2<i8>) -> Rc<Self::State>;
-    fn transition(&mut self, game_state: Rc<Self::State>, action: (usize,usize)) -> Rc<Self::State>;
-}
\ No newline at end of file
+    type Action: Clone + Eq + Hash + Debug;
+    type Board: Clone + Eq + Hash + Debug;
+    type State: GameState<Action = Self::Action, Board = Self::Board>;
+    fn get_state(&mut self, board: &Self::Board) -> Rc<Self::State>;
+    fn transition(&mut self, game_state: Rc<Self::State>, action: Self::Action) -> Rc<Self::State>;
+}
+
+/// A game with hidden information, e.g. cards in hand, for `ISMCTS`.
+pub trait InformationSetGame: Game {
+    type InfoSet: Clone + Eq + Hash + Debug;
+
+    /// What `player` can observe of `state`. States a player can't tell apart share an info set.
+    fn information_set(&self, state: &Self::State, player: i32) -> Self::InfoSet;
+
+    /// A full state drawn from those `player` can't tell apart from `state`, filling in what they
+    /// can't see, like the other players' hands.
+    fn determinize(&mut self, state: &Self::State, player: i32, rng: &mut StdRng) -> Rc<Self::State>;
+}
+
+/// A game in which several players choose at once and the joint action is what gets played, for
+/// `SimultaneousMCTS`. `Game::Action` is the joint action, so `all_legal_actions` lists every
+/// combination and evaluators can play the game like any other.
+pub trait SimultaneousGame: Game {
+    type PlayerAction: Clone + Eq + Hash + Debug;
+
+    /// `(player, actions open to them)` for every player choosing at `state`.
+    fn player_actions(&self, state: &Self::State) -> Vec<(i32, Vec<Self::PlayerAction>)>;
+
+    /// The joint action for one action per player, in `player_actions` order.
+    fn joint_action(&self, actions: &[Self::PlayerAction]) -> Self::Action;
+}
+
+/// Optional view of a state as a grid of signed bytes, for display and feature extraction.
+pub trait ToArray2 {
+    fn to_array2(&self) -> Array2<i8>; // each cell holds the player whose piece is on it, or 0
+
+    /// Network input planes from `player`'s side, shaped (2, rows, cols): 1 where their pieces
+    /// are in the first plane, where their opponents' are in the second, 0 elsewhere.
+    fn to_planes(&self, player: i32) -> Array3<f32> {
+        let grid = self.to_array2();
+        let mut planes = Array3::zeros((2, grid.nrows(), grid.ncols()));
+        for ((row, col), &cell) in grid.indexed_iter() {
+            if cell != 0 {
+                let plane = if cell as i32 == player { 0 } else { 1 };
+                planes[[plane, row, col]] = 1.;
+            }
+        }
+        planes
+    }
+}
@@ -3,7 +3,7 @@ use std::fmt::{Display,Formatter,Result};
 use std::rc::Rc;
 use ndarray::{Array2,Axis,s};
 use wyhash2::WyHash;
-use crate::game::{Game,GameState};
+use crate::game::{Game,GameState,ToArray2};
 
 pub struct Connect4 {
     pub game_states: HashMap<Array2<i8>, Rc<Connect4State>,WyHash>
@@ -15,7 +15,15 @@ impl Connect4 {
     }
 }
 
+impl Default for Connect4 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Game for Connect4 {
+    type Board = Array2<i8>;
+    type Action = usize; // the column to drop a piece into
     type State = Connect4State;
 
     fn get_state(&mut self, board: &Array2<i8>) -> Rc<Connect4State> {
@@ -28,9 +36,10 @@ impl Game for Connect4 {
         }
     }
 
-    fn transition(&mut self, game_state: Rc<Connect4State>, action: (usize, usize)) -> Rc<Connect4State> {
+    fn transition(&mut self, game_state: Rc<Connect4State>, action: usize) -> Rc<Connect4State> {
         let mut new_state = game_state.state.clone();
-        new_state[action] = game_state.player as i8;
+        let row = Connect4State::drop_row(&new_state, action).expect("Column is full");
+        new_state[[row, action]] = game_state.player as i8;
         self.get_state(&new_state)
     }
 }
@@ -41,7 +50,7 @@ pub struct Connect4State {
     pub player: i32,
     pub result: Option<Vec<(i32,i32)>>,
     pub is_terminal: bool,
-    pub all_legal_actions: Option<Vec<(usize,usize)>>
+    pub all_legal_actions: Option<Vec<usize>>
 }
 
 impl Connect4State {
@@ -49,15 +58,11 @@ impl Connect4State {
         let player = if state.sum() <= 0 { 1 } else { -1 };
         let result = Connect4State::game_result(&state);
         let is_terminal = result.is_some();
-        let all_legal_actions = {
-            let mut actions = Vec::new();
-            for j in 0..state.ncols() {
-                if let Some(i) = (0..state.nrows()).rev().find(|&i| state[[i,j]] == 0) {
-                    actions.push((i,j));
-                }
-            }
-            Some(actions)
-        };
+        let all_legal_actions = Some(
+            (0..state.ncols())
+                .filter(|&j| Connect4State::drop_row(&state, j).is_some())
+                .collect()
+        );
         Connect4State {
             state,
             player,
@@ -67,6 +72,11 @@ impl Connect4State {
         }
     }
     
+    /// The row a piece dropped into column `j` lands in, `None` if the column is full.
+    fn drop_row(state: &Array2<i8>, j: usize) -> Option<usize> {
+        (0..state.nrows()).rev().find(|&i| state[[i,j]] == 0)
+    }
+
     #[inline]
     fn game_result(state: &Array2<i8>) -> Option<Vec<(i32,i32)>> {
         let mut has_empty_cells = false;
@@ -103,11 +113,14 @@ impl Connect4State {
 
 impl Display for Connect4State {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        write!(f, "{}", self.state)
+        write!(f, "{}", self.to_array2())
     }
 }
 
 impl GameState for Connect4State {
+    type Board = Array2<i8>;
+    type Action = usize;
+
     fn state(&self) -> &Array2<i8> {
         &self.state
     }
@@ -123,7 +136,13 @@ impl GameState for Connect4State {
         &self.result
     }
 
-    fn all_legal_actions(&self) -> &Option<Vec<(usize, usize)>> {
+    fn all_legal_actions(&self) -> &Option<Vec<usize>> {
         &self.all_legal_actions
     }
-}
\ No newline at end of file
+}
+
+impl ToArray2 for Connect4State {
+    fn to_array2(&self) -> Array2<i8> {
+        self.state.clone()
+    }
+}
@@ -0,0 +1,109 @@
+use std::rc::Rc;
+use crate::game::{Game,GameState};
+
+/// Dots and Boxes on a grid of `rows` x `cols` boxes. Lines are numbered horizontal ones first,
+/// then vertical ones, each row by row, so two boxes side by side are
+///
+/// ```text
+///     0   1
+///   4   5   6
+///     2   3
+/// ```
+///
+/// A player who completes a box moves again. The board is (drawn lines as bits, player to move,
+/// player 1's boxes minus player -1's).
+pub struct DotsAndBoxes {
+    pub rows: usize,
+    pub cols: usize,
+    boxes: Vec<[u8; 4]> // the lines around every box
+}
+
+impl DotsAndBoxes {
+    pub fn new(rows: usize, cols: usize) -> Self {
+        let horizontal = (rows + 1) * cols;
+        assert!(horizontal + rows * (cols + 1) <= 64, "Lines have to fit in a u64");
+        let boxes = (0..rows)
+            .flat_map(|row| (0..cols).map(move |col| {
+                let left = horizontal + row * (cols + 1) + col;
+                [row * cols + col, (row + 1) * cols + col, left, left + 1].map(|line| line as u8)
+            }))
+            .collect();
+        DotsAndBoxes { rows, cols, boxes }
+    }
+
+    pub fn lines(&self) -> usize {
+        (self.rows + 1) * self.cols + self.rows * (self.cols + 1)
+    }
+}
+
+impl Game for DotsAndBoxes {
+    type Board = (u64, i32, i32);
+    type Action = u8; // the line to draw
+    type State = DotsAndBoxesState;
+
+    fn get_state(&mut self, board: &(u64, i32, i32)) -> Rc<DotsAndBoxesState> {
+        Rc::new(DotsAndBoxesState::new(*board, self.lines()))
+    }
+
+    fn transition(&mut self, game_state: Rc<DotsAndBoxesState>, action: u8) -> Rc<DotsAndBoxesState> {
+        let (lines, player, score) = game_state.state;
+        let lines = lines | (1 << action);
+        let completed = self.boxes
+            .iter()
+            .filter(|sides| sides.contains(&action) && sides.iter().all(|side| lines & (1 << side) != 0))
+            .count() as i32;
+        let next_player = if completed > 0 { player } else { -player };
+        self.get_state(&(lines, next_player, score + completed * player))
+    }
+}
+
+#[derive(Debug,PartialEq,Eq,std::hash::Hash)]
+pub struct DotsAndBoxesState {
+    pub state: (u64, i32, i32),
+    pub player: i32,
+    pub result: Option<Vec<(i32,i32)>>,
+    pub is_terminal: bool,
+    pub all_legal_actions: Option<Vec<u8>>
+}
+
+impl DotsAndBoxesState {
+    /// The state for `state` on a grid with `lines` lines in all.
+    pub fn new(state: (u64, i32, i32), lines: usize) -> DotsAndBoxesState {
+        let (drawn, player, score) = state;
+        let all_legal_actions: Vec<u8> = (0..lines as u8).filter(|line| drawn & (1 << line) == 0).collect();
+        let is_terminal = all_legal_actions.is_empty();
+        let result = is_terminal.then(|| vec![(1, score.signum()), (-1, -score.signum())]);
+        DotsAndBoxesState {
+            state,
+            player,
+            result,
+            is_terminal,
+            all_legal_actions: Some(all_legal_actions)
+        }
+    }
+}
+
+impl GameState for DotsAndBoxesState {
+    type Board = (u64, i32, i32);
+    type Action = u8;
+
+    fn state(&self) -> &(u64, i32, i32) {
+        &self.state
+    }
+
+    fn is_terminal(&self) -> &bool {
+        &self.is_terminal
+    }
+
+    fn player(&self) -> &i32 {
+        &self.player
+    }
+
+    fn result(&self) -> &Option<Vec<(i32,i32)>> {
+        &self.result
+    }
+
+    fn all_legal_actions(&self) -> &Option<Vec<u8>> {
+        &self.all_legal_actions
+    }
+}
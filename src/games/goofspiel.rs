@@ -0,0 +1,107 @@
+use std::rc::Rc;
+use crate::game::{Game,GameState,SimultaneousGame};
+
+/// Goofspiel: both players hold the cards 1 to n and the prizes are turned over in a fixed order.
+/// Each round both bid a card at once, the higher bid takes the prize and a tie takes nothing.
+/// Whoever took more prize points wins. The joint action is (player 1's bid, player -1's bid).
+pub struct Goofspiel;
+
+#[derive(Debug,Clone,PartialEq,Eq,Hash)]
+pub struct GoofspielBoard {
+    pub hands: [Vec<u8>; 2], // player 1's, player -1's
+    pub prizes: Vec<u8>, // the current prize first
+    pub score: i32 // player 1's points minus player -1's
+}
+
+impl GoofspielBoard {
+    /// The start of a game with the cards 1 to `cards`, the prizes turned over from the highest.
+    pub fn new(cards: u8) -> GoofspielBoard {
+        let hand: Vec<u8> = (1..=cards).collect();
+        GoofspielBoard { hands: [hand.clone(), hand.clone()], prizes: hand.into_iter().rev().collect(), score: 0 }
+    }
+}
+
+impl Game for Goofspiel {
+    type Board = GoofspielBoard;
+    type Action = (u8, u8);
+    type State = GoofspielState;
+
+    fn get_state(&mut self, board: &GoofspielBoard) -> Rc<GoofspielState> {
+        Rc::new(GoofspielState::new(board.clone()))
+    }
+
+    fn transition(&mut self, game_state: Rc<GoofspielState>, action: (u8, u8)) -> Rc<GoofspielState> {
+        let mut board = game_state.state.clone();
+        let (bid, other) = action;
+        board.hands[0].retain(|&card| card != bid);
+        board.hands[1].retain(|&card| card != other);
+        let prize = board.prizes.remove(0) as i32;
+        board.score += prize * (bid as i32 - other as i32).signum();
+        self.get_state(&board)
+    }
+}
+
+impl SimultaneousGame for Goofspiel {
+    type PlayerAction = u8;
+
+    fn player_actions(&self, state: &GoofspielState) -> Vec<(i32, Vec<u8>)> {
+        if state.is_terminal {
+            return vec![];
+        }
+        vec![(1, state.state.hands[0].clone()), (-1, state.state.hands[1].clone())]
+    }
+
+    fn joint_action(&self, actions: &[u8]) -> (u8, u8) {
+        (actions[0], actions[1])
+    }
+}
+
+#[derive(Debug,PartialEq,Eq,std::hash::Hash)]
+pub struct GoofspielState {
+    pub state: GoofspielBoard,
+    pub result: Option<Vec<(i32,i32)>>,
+    pub is_terminal: bool,
+    pub all_legal_actions: Option<Vec<(u8,u8)>>
+}
+
+impl GoofspielState {
+    pub fn new(state: GoofspielBoard) -> GoofspielState {
+        let is_terminal = state.prizes.is_empty();
+        let result = is_terminal.then(|| vec![(1, state.score.signum()), (-1, -state.score.signum())]);
+        let all_legal_actions = Some(state.hands[0]
+            .iter()
+            .flat_map(|&bid| state.hands[1].iter().map(move |&other| (bid, other)))
+            .collect());
+        GoofspielState {
+            state,
+            result,
+            is_terminal,
+            all_legal_actions
+        }
+    }
+}
+
+impl GameState for GoofspielState {
+    type Board = GoofspielBoard;
+    type Action = (u8, u8);
+
+    fn state(&self) -> &GoofspielBoard {
+        &self.state
+    }
+
+    fn is_terminal(&self) -> &bool {
+        &self.is_terminal
+    }
+
+    fn player(&self) -> &i32 {
+        &1 // both players move at once
+    }
+
+    fn result(&self) -> &Option<Vec<(i32,i32)>> {
+        &self.result
+    }
+
+    fn all_legal_actions(&self) -> &Option<Vec<(u8,u8)>> {
+        &self.all_legal_actions
+    }
+}
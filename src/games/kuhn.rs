@@ -0,0 +1,113 @@
+use std::rc::Rc;
+use rand::Rng;
+use rand::rngs::StdRng;
+use crate::game::{Game,GameState,InformationSetGame};
+use KuhnAction::{Bet,Pass};
+
+/// Kuhn poker: a jack (0), queen (1) and king (2), one card each, both players ante 1. Player 1
+/// passes or bets 1. After a pass player -1 may pass to a showdown or bet, and a bet is either
+/// called (Bet) for a showdown or folded (Pass). The higher card takes the pot.
+pub struct Kuhn;
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
+pub enum KuhnAction {
+    Pass,
+    Bet
+}
+
+#[derive(Debug,Clone,PartialEq,Eq,Hash)]
+pub struct KuhnBoard {
+    pub cards: [u8; 2], // player 1's, player -1's
+    pub history: Vec<KuhnAction>
+}
+
+impl Game for Kuhn {
+    type Board = KuhnBoard;
+    type Action = KuhnAction;
+    type State = KuhnState;
+
+    fn get_state(&mut self, board: &KuhnBoard) -> Rc<KuhnState> {
+        Rc::new(KuhnState::new(board.clone()))
+    }
+
+    fn transition(&mut self, game_state: Rc<KuhnState>, action: KuhnAction) -> Rc<KuhnState> {
+        let mut board = game_state.state.clone();
+        board.history.push(action);
+        self.get_state(&board)
+    }
+}
+
+impl InformationSetGame for Kuhn {
+    type InfoSet = (u8, Vec<KuhnAction>); // own card and the betting so far
+
+    fn information_set(&self, state: &KuhnState, player: i32) -> (u8, Vec<KuhnAction>) {
+        let seat = if player == 1 { 0 } else { 1 };
+        (state.state.cards[seat], state.state.history.clone())
+    }
+
+    fn determinize(&mut self, state: &KuhnState, player: i32, rng: &mut StdRng) -> Rc<KuhnState> {
+        let seat = if player == 1 { 0 } else { 1 };
+        let mut board = state.state.clone();
+        let unseen: Vec<u8> = (0..3).filter(|&card| card != board.cards[seat]).collect();
+        board.cards[1 - seat] = unseen[rng.gen_range(0..unseen.len())];
+        self.get_state(&board)
+    }
+}
+
+#[derive(Debug,PartialEq,Eq,std::hash::Hash)]
+pub struct KuhnState {
+    pub state: KuhnBoard,
+    pub player: i32,
+    pub result: Option<Vec<(i32,i32)>>,
+    pub is_terminal: bool,
+    pub all_legal_actions: Option<Vec<KuhnAction>>
+}
+
+impl KuhnState {
+    pub fn new(state: KuhnBoard) -> KuhnState {
+        let showdown = if state.cards[0] > state.cards[1] { 1 } else { -1 };
+        let player_1_wins = match state.history.as_slice() {
+            [Pass, Pass] => Some(showdown),
+            [Bet, Bet] | [Pass, Bet, Bet] => Some(2 * showdown),
+            [Bet, Pass] => Some(1),
+            [Pass, Bet, Pass] => Some(-1),
+            _ => None
+        };
+        let result = player_1_wins.map(|won| vec![(1, won), (-1, -won)]);
+        let is_terminal = result.is_some();
+        let all_legal_actions = Some(if is_terminal { vec![] } else { vec![Pass, Bet] });
+        let player = if state.history.len().is_multiple_of(2) { 1 } else { -1 };
+        KuhnState {
+            state,
+            player,
+            result,
+            is_terminal,
+            all_legal_actions
+        }
+    }
+}
+
+impl GameState for KuhnState {
+    type Board = KuhnBoard;
+    type Action = KuhnAction;
+
+    fn state(&self) -> &KuhnBoard {
+        &self.state
+    }
+
+    fn is_terminal(&self) -> &bool {
+        &self.is_terminal
+    }
+
+    fn player(&self) -> &i32 {
+        &self.player
+    }
+
+    fn result(&self) -> &Option<Vec<(i32,i32)>> {
+        &self.result
+    }
+
+    fn all_legal_actions(&self) -> &Option<Vec<KuhnAction>> {
+        &self.all_legal_actions
+    }
+}
@@ -1,2 +1,6 @@
 pub mod tictactoe;
-pub mod connect4;
\ No newline at end of file
+pub mod connect4;
+pub mod dots_and_boxes;
+pub mod pig;
+pub mod kuhn;
+pub mod goofspiel;
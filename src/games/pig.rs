@@ -0,0 +1,146 @@
+use std::hash::{Hash,Hasher};
+use std::rc::Rc;
+use crate::game::{Game,GameState};
+
+/// Pig to `target`: the player to move rolls a die as often as they like, adding each roll to a
+/// turn total, and may hold to bank it. Rolling a 1 loses the turn total and passes the turn.
+pub struct Pig {
+    pub target: i32
+}
+
+impl Pig {
+    pub fn new(target: i32) -> Self {
+        Pig { target }
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
+pub enum PigAction {
+    Roll,
+    Hold,
+    Face(u8) // the chance outcome of a roll
+}
+
+#[derive(Debug,Clone,PartialEq,Eq,Hash)]
+pub struct PigBoard {
+    pub scores: [i32; 2], // player 1's, player -1's
+    pub turn_total: i32,
+    pub player: i32,
+    pub rolling: bool // waiting on the die
+}
+
+impl Game for Pig {
+    type Board = PigBoard;
+    type Action = PigAction;
+    type State = PigState;
+
+    fn get_state(&mut self, board: &PigBoard) -> Rc<PigState> {
+        Rc::new(PigState::new(board.clone(), self.target))
+    }
+
+    fn transition(&mut self, game_state: Rc<PigState>, action: PigAction) -> Rc<PigState> {
+        let mut board = game_state.state.clone();
+        let index = if board.player == 1 { 0 } else { 1 };
+        match action {
+            PigAction::Roll => board.rolling = true,
+            PigAction::Hold => {
+                board.scores[index] += board.turn_total;
+                board.turn_total = 0;
+                board.player = -board.player;
+            }
+            PigAction::Face(1) => {
+                board.turn_total = 0;
+                board.player = -board.player;
+                board.rolling = false;
+            }
+            PigAction::Face(face) => {
+                board.turn_total += face as i32;
+                board.rolling = false;
+            }
+        }
+        self.get_state(&board)
+    }
+}
+
+#[derive(Debug)]
+pub struct PigState {
+    pub state: PigBoard,
+    pub result: Option<Vec<(i32,i32)>>,
+    pub is_terminal: bool,
+    pub all_legal_actions: Option<Vec<PigAction>>,
+    pub chance_outcomes: Option<Vec<(PigAction,f64)>>
+}
+
+impl PigState {
+    /// The state for `state` in a game played to `target`.
+    pub fn new(state: PigBoard, target: i32) -> PigState {
+        let result = if state.scores[0] >= target {
+            Some(vec![(1, 1), (-1, -1)])
+        } else if state.scores[1] >= target {
+            Some(vec![(1, -1), (-1, 1)])
+        } else {
+            None
+        };
+        let is_terminal = result.is_some();
+        let all_legal_actions = Some(if is_terminal || state.rolling {
+            vec![]
+        } else if state.turn_total > 0 {
+            vec![PigAction::Roll, PigAction::Hold]
+        } else {
+            vec![PigAction::Roll]
+        });
+        let chance_outcomes = (state.rolling && !is_terminal)
+            .then(|| (1..=6).map(|face| (PigAction::Face(face), 1. / 6.)).collect());
+        PigState {
+            state,
+            result,
+            is_terminal,
+            all_legal_actions,
+            chance_outcomes
+        }
+    }
+}
+
+// everything else follows from the board, and the probabilities can't be hashed
+impl PartialEq for PigState {
+    fn eq(&self, other: &Self) -> bool {
+        self.state == other.state
+    }
+}
+
+impl Eq for PigState {}
+
+impl Hash for PigState {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        self.state.hash(hasher)
+    }
+}
+
+impl GameState for PigState {
+    type Board = PigBoard;
+    type Action = PigAction;
+
+    fn state(&self) -> &PigBoard {
+        &self.state
+    }
+
+    fn is_terminal(&self) -> &bool {
+        &self.is_terminal
+    }
+
+    fn player(&self) -> &i32 {
+        &self.state.player
+    }
+
+    fn result(&self) -> &Option<Vec<(i32,i32)>> {
+        &self.result
+    }
+
+    fn all_legal_actions(&self) -> &Option<Vec<PigAction>> {
+        &self.all_legal_actions
+    }
+
+    fn chance_outcomes(&self) -> &Option<Vec<(PigAction,f64)>> {
+        &self.chance_outcomes
+    }
+}
@@ -3,7 +3,7 @@ use std::fmt::{Display,Formatter,Result};
 use std::rc::Rc;
 use ndarray::{Array2,Axis,s};
 use wyhash2::WyHash;
-use crate::game::{Game,GameState};
+use crate::game::{Game,GameState,ToArray2};
 
 pub struct TicTacToe {
     pub game_states: HashMap<Array2<i8>, Rc<TicTacToeState>,WyHash>
@@ -15,7 +15,15 @@ impl TicTacToe {
     }
 }
 
+impl Default for TicTacToe {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Game for TicTacToe {
+    type Board = Array2<i8>;
+    type Action = (usize, usize);
     type State = TicTacToeState;
 
     fn get_state(&mut self, board: &Array2<i8>) -> Rc<TicTacToeState> {
@@ -99,11 +107,14 @@ impl TicTacToeState {
 
 impl Display for TicTacToeState {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        write!(f, "{}", self.state)
+        write!(f, "{}", self.to_array2())
     }
 }
 
 impl GameState for TicTacToeState {
+    type Board = Array2<i8>;
+    type Action = (usize, usize);
+
     fn state(&self) -> &Array2<i8> {
         &self.state
     }
@@ -122,4 +133,10 @@ impl GameState for TicTacToeState {
     fn all_legal_actions(&self) -> &Option<Vec<(usize, usize)>> {
         &self.all_legal_actions
     }
-}
\ No newline at end of file
+}
+
+impl ToArray2 for TicTacToeState {
+    fn to_array2(&self) -> Array2<i8> {
+        self.state.clone()
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use wyhash2::WyHash;
+use crate::evaluator::{Evaluation,Evaluator,RandomRollout};
+use crate::game::{GameState,InformationSetGame};
+use crate::mcts::{ActionStats,MCTSConfig,MoveSelection,NodeId,select_action,visit_policy,zero_sum_reward};
+
+/// Statistics for one action out of an information set. Not every action is legal in every state
+/// of the info set, so an edge also counts how often it was available.
+#[derive(Debug, Clone)]
+pub struct ISEdge<A> {
+    pub action: A,
+    pub visits: u32,
+    pub availability: u32, // playouts through the parent in which the action was legal
+    pub total_value: f64 // sum of the playouts' rewards for the parent's player to move
+}
+
+impl<A> ISEdge<A> {
+    /// Mean reward for the parent's player to move over the edge's visits.
+    pub fn Q(&self) -> f64 {
+        if self.visits == 0 { 0. } else { self.total_value / self.visits as f64 }
+    }
+}
+
+/// One information set of the player to move. Edges are added as determinizations turn up
+/// legal actions, in the order they are first seen.
+#[derive(Debug, Clone)]
+pub struct ISNode<A> {
+    pub player: i32,
+    pub N: u32, // visit count
+    pub edges: Vec<ISEdge<A>>
+}
+
+/// Single-observer Information Set MCTS. Every playout samples a determinization of the root state
+/// that the root player can't tell apart from the real one, then walks the graph of information
+/// sets: each node is the info set of the player to move, shared by every state in it. Selection
+/// only considers the actions legal in the current determinization, chance outcomes are sampled,
+/// and values are plain means rather than the MCGS regularized Q.
+pub struct ISMCTS<G: InformationSetGame, E: Evaluator<G> = RandomRollout> {
+    pub root: NodeId,
+    pub root_state: Rc<G::State>, // only the root player's view of it is used
+    pub nodes: Vec<ISNode<G::Action>>,
+    pub node_ids: HashMap<(i32,G::InfoSet),NodeId,WyHash>, // (player to move, their info set), as two players can see the same
+    pub game: G,
+    pub evaluator: E,
+    pub config: MCTSConfig, // uses c_puct and seed
+    rng: StdRng
+}
+
+impl<G: InformationSetGame> ISMCTS<G, RandomRollout> {
+
+    pub fn new(game: G, root_state: Rc<G::State>) -> Self {
+        ISMCTS::with_evaluator(game, root_state, RandomRollout, MCTSConfig::default())
+    }
+}
+
+impl<G: InformationSetGame, E: Evaluator<G>> ISMCTS<G, E> {
+
+    pub fn with_evaluator(game: G, root_state: Rc<G::State>, evaluator: E, config: MCTSConfig) -> Self {
+        let mut ismcts = ISMCTS {
+            root: NodeId(0),
+            root_state: root_state.clone(),
+            nodes: Vec::new(),
+            node_ids: HashMap::with_hasher(WyHash::with_seed(config.seed)),
+            game,
+            evaluator,
+            rng: StdRng::seed_from_u64(config.seed),
+            config
+        };
+        ismcts.root = ismcts.get_node(&root_state);
+        ismcts
+    }
+
+    pub fn node(&self, id: NodeId) -> &ISNode<G::Action> {
+        &self.nodes[id.0]
+    }
+
+    pub fn root_node(&self) -> &ISNode<G::Action> {
+        self.node(self.root)
+    }
+
+    /// The id of the node for the info set of the player to move in `state`, adding it if needed.
+    pub fn get_node(&mut self, state: &G::State) -> NodeId {
+        let player = *state.player();
+        let key = (player, self.game.information_set(state, player));
+        if let Some(&id) = self.node_ids.get(&key) {
+            id
+        } else {
+            let id = NodeId(self.nodes.len());
+            self.nodes.push(ISNode { player, N: 0, edges: Vec::new() });
+            self.node_ids.insert(key, id);
+            id
+        }
+    }
+
+    /// One playout: determinize, select down to the first edge never visited before, evaluate the
+    /// state it leads to and back the reward up the edges taken.
+    pub fn run(&mut self) {
+        let observer = *self.root_state.player();
+        let mut state = self.game.determinize(&self.root_state, observer, &mut self.rng);
+        let mut path: Vec<(NodeId, usize)> = Vec::new();
+        loop {
+            state = self.resolve_chance(state);
+            if *state.is_terminal() {
+                break;
+            }
+            let id = self.get_node(&state);
+            let edge_index = self.select_edge(id, &state);
+            path.push((id, edge_index));
+            let edge = &self.nodes[id.0].edges[edge_index];
+            let first_visit = edge.visits == 0;
+            state = self.game.transition(state, edge.action.clone());
+            if first_visit {
+                break;
+            }
+        }
+        let reward_map = if *state.is_terminal() {
+            Evaluation::<G::Action>::terminal(&*state).values
+        } else {
+            self.evaluator.evaluate(&mut self.game, state, &mut self.rng).values
+        };
+        self.backprop(&path, &reward_map);
+    }
+
+    pub fn search(&mut self, n: u32) {
+        for _ in 0..n { self.run() }
+    }
+
+    /// Samples chance outcomes until a player is to move or the game is over.
+    fn resolve_chance(&mut self, mut state: Rc<G::State>) -> Rc<G::State> {
+        while !*state.is_terminal() {
+            let Some(outcomes) = state.chance_outcomes() else { break };
+            let outcome = outcomes.choose_weighted(&mut self.rng, |(_, p)| *p).expect("Bad chance outcomes").0.clone();
+            state = self.game.transition(state, outcome);
+        }
+        state
+    }
+
+    /// Adds edges for actions seen for the first time, charges every action legal in `state` an
+    /// availability, and picks an unvisited legal edge if there is one, else the best by PUCT.
+    fn select_edge(&mut self, id: NodeId, state: &G::State) -> usize {
+        let legal = state.all_legal_actions().as_ref().expect("No legal actions for a non-terminal state?");
+        let node = &mut self.nodes[id.0];
+        let mut available = Vec::with_capacity(legal.len());
+        for action in legal {
+            let edge_index = match node.edges.iter().position(|edge| edge.action == *action) {
+                Some(edge_index) => edge_index,
+                None => {
+                    node.edges.push(ISEdge { action: action.clone(), visits: 0, availability: 0, total_value: 0. });
+                    node.edges.len() - 1
+                }
+            };
+            node.edges[edge_index].availability += 1;
+            available.push(edge_index);
+        }
+        let node = &self.nodes[id.0];
+        if let Some(&unvisited) = available.iter().find(|&&edge_index| node.edges[edge_index].visits == 0) {
+            return unvisited;
+        }
+        available
+            .into_iter()
+            .map(|edge_index| (edge_index, self.PUCT(&node.edges[edge_index])))
+            .max_by(|(_, puct_a), (_, puct_b)| puct_a.partial_cmp(puct_b).expect("Comparison failed due to NaN"))
+            .expect("Called select on no legal actions")
+            .0
+    }
+
+    /// PUCT with the edge's availability in place of the parent's visit count, so actions that are
+    /// rarely legal aren't over-explored.
+    pub fn PUCT(&self, edge: &ISEdge<G::Action>) -> f64 {
+        edge.Q() + self.config.c_puct * f64::sqrt(edge.availability as f64) / (1 + edge.visits) as f64
+    }
+
+    fn backprop(&mut self, path: &[(NodeId, usize)], reward_map: &HashMap<i32, f64>) {
+        for &(id, edge_index) in path {
+            let node = &mut self.nodes[id.0];
+            let reward = zero_sum_reward(reward_map, node.player);
+            node.N += 1;
+            let edge = &mut node.edges[edge_index];
+            edge.visits += 1;
+            edge.total_value += reward;
+        }
+    }
+
+    /// `(action, edge visits, mean reward for the root player)` for every edge out of the root.
+    pub fn root_stats(&self) -> Vec<ActionStats<G::Action>> {
+        self.root_node()
+            .edges
+            .iter()
+            .map(|edge| (edge.action.clone(), edge.visits, edge.Q()))
+            .collect()
+    }
+
+    /// See `MCTS::best_action`.
+    pub fn best_action(&self, rule: MoveSelection) -> Option<G::Action> {
+        select_action(&self.root_stats(), rule)
+    }
+
+    /// See `MCTS::policy`.
+    pub fn policy(&self, temperature: f64) -> Vec<(G::Action, f64)> {
+        visit_policy(&self.root_stats(), temperature)
+    }
+}
@@ -1,3 +1,9 @@
+#![allow(non_snake_case)] // N, Q and PUCT follow the notation in montecarlographsearch.md
+pub mod evaluator;
 pub mod game;
 pub mod games;
-pub mod mcts;
\ No newline at end of file
+pub mod ismcts;
+pub mod mcts;
+pub mod parallel;
+pub mod puzzle;
+pub mod simultaneous;
\ No newline at end of file
@@ -1,196 +1,1218 @@
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap,HashSet};
+use std::hash::BuildHasher;
+use std::mem::size_of;
 use std::rc::Rc;
-use rand::prelude::SliceRandom;
-use rand::thread_rng;
+use std::time::{Duration,Instant};
+use rand::{Rng,SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand_distr::{Dirichlet,Distribution,Gumbel};
 use wyhash2::WyHash;
+use crate::evaluator::{Evaluation,Evaluator,RandomRollout};
 use crate::game::{Game,GameState};
 
+/// `(action, edge visits, child Q)` for one edge out of the root.
+pub type ActionStats<A> = (A, u32, f64);
+
+/// Rule for picking the move to play from the root once search is done.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MoveSelection {
+    /// Most edge visits, the "robust child".
+    MaxVisits,
+    /// Highest child Q, the "max child".
+    MaxQ,
+    /// Highest lower confidence bound `Q - c / sqrt(N(s,a))`, the "secure child". Unvisited
+    /// edges have no bound and come last.
+    Secure(f64),
+}
+
+/// Temperature by move number for `MCTS::sample_action`, e.g. `[(0, 1.), (30, 0.)]` to sample in
+/// proportion to visits for the first 30 moves of a game and play the most visited move after.
+/// Each `(from_move, temperature)` holds until the next one, and moves before the first get 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemperatureSchedule(pub Vec<(u32, f64)>);
+
+impl TemperatureSchedule {
+    pub fn temperature(&self, move_number: u32) -> f64 {
+        self.0
+            .iter()
+            .filter(|(from_move, _)| *from_move <= move_number)
+            .max_by_key(|(from_move, _)| *from_move)
+            .map_or(1., |&(_, temperature)| temperature)
+    }
+}
+
+/// AlphaZero root noise: whenever a node becomes the expanded root, its priors are mixed with a
+/// Dirichlet(alpha) sample as `(1 - epsilon) * prior + epsilon * noise`, so self-play explores moves
+/// the prior would rule out. Smaller alphas concentrate the noise on fewer moves.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirichletNoise {
+    pub alpha: f64,
+    pub epsilon: f64
+}
+
+/// Rapid Action Value Estimation: PUCT blends each edge's Q with its all-moves-as-first value as
+/// `(1 - beta) * Q + beta * AMAF`, with `beta = sqrt(equivalence / (3 * N(s,a) + equivalence))`
+/// decaying as the edge gets visits of its own. Needs actions that mean the same move whenever
+/// they're played, like placing on a grid point, and an evaluator that reports its rollout's
+/// `moves`, like `RandomRollout`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rave {
+    pub equivalence: f64 // the edge visits at which Q and AMAF are weighted about equally
+}
+
+impl Rave {
+    pub fn beta(&self, visits: u32) -> f64 {
+        f64::sqrt(self.equivalence / (3. * visits as f64 + self.equivalence))
+    }
+}
+
+/// How `MCTS::search` spends its playouts at the root.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RootSelection {
+    /// The root picks edges by PUCT like every other node.
+    PUCT,
+    /// Gumbel AlphaZero: sequential halving over a Gumbel-Top-k sample of the prior. Play
+    /// `MCTS::gumbel_action` and train on `MCTS::completed_q_policy`, the paper uses 16, 50 and 1.
+    Gumbel { considered_actions: usize, c_visit: f64, c_scale: f64 }
+}
+
+/// Budget for `MCTS::search_with_limits`. Search stops as soon as any limit is reached,
+/// `None` leaves that dimension unbounded.
+#[derive(Debug, Clone, Default)]
+pub struct SearchLimits {
+    pub playouts: Option<u32>,
+    pub time: Option<Duration>,
+    pub max_nodes: Option<usize>,
+    pub max_memory: Option<usize>, // bytes, as reported by MCTS::memory_estimate
+    /// Stop once the most visited root move can't be overtaken in the remaining budget.
+    pub early_stop: bool
+}
+
+impl SearchLimits {
+    /// Whether any limit is set that is sure to stop the search, `early_stop` alone isn't.
+    pub fn is_bounded(&self) -> bool {
+        self.playouts.is_some() || self.time.is_some() || self.max_nodes.is_some() || self.max_memory.is_some()
+    }
+}
+
+/// How `MCTS::backprop` recomputes N and Q for the nodes on a playout's path.
+/// See "Incremental vs Idempotent Updates" in montecarlographsearch.md.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backup {
+    /// Recompute from every child's current Q. O(branching factor) per node.
+    Idempotent,
+    /// Keep a running sum of edge visits times the child Q last seen through each edge, and only
+    /// refresh the edges the playout went through. Edges off the path keep stale Qs until visited.
+    Incremental
+}
+
+/// Game-theoretic value of a node proven by `MCTSConfig::solver`, from the same perspective as
+/// its Q: the opponent of the player to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Proof {
+    Win,
+    Draw,
+    Loss
+}
+
+impl Proof {
+    fn from_value(value: f64) -> Proof {
+        if value > 0. { Proof::Win } else if value < 0. { Proof::Loss } else { Proof::Draw }
+    }
+
+    /// The proof from the other player's side.
+    fn flip(self) -> Proof {
+        match self {
+            Proof::Win => Proof::Loss,
+            Proof::Draw => Proof::Draw,
+            Proof::Loss => Proof::Win
+        }
+    }
+
+    pub fn value(&self) -> f64 {
+        match self {
+            Proof::Win => 1.,
+            Proof::Draw => 0.,
+            Proof::Loss => -1.
+        }
+    }
+}
+
+/// Backs up a blend of the win/loss and the margin instead of raw utilities, like a score
+/// difference: `(1 - weight) * sign(u) + weight * tanh(u / scale)`. Wins still come first, but
+/// among them bigger margins are worth more, and every value stays in [-1, 1].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarginBlend {
+    pub weight: f64,
+    pub scale: f64 // a margin of `scale` earns about three quarters of `weight`
+}
+
+impl MarginBlend {
+    pub fn blend(&self, utility: f64) -> f64 {
+        (1. - self.weight) * Proof::from_value(utility).value() + self.weight * (utility / self.scale).tanh()
+    }
+}
+
+/// Running statistics of the rewards backed up through a node.
+#[derive(Debug, Clone, Default)]
+pub struct ValueStats {
+    pub count: u32,
+    pub mean: f64,
+    pub min: f64,
+    pub max: f64,
+    sum_of_squared_deviations: f64 // Welford's M2
+}
+
+impl ValueStats {
+    pub fn push(&mut self, value: f64) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.count += 1;
+        let deviation = value - self.mean;
+        self.mean += deviation / self.count as f64;
+        self.sum_of_squared_deviations += deviation * (value - self.mean);
+    }
+
+    /// Population variance, 0 until there are two values.
+    pub fn variance(&self) -> f64 {
+        if self.count < 2 { 0. } else { self.sum_of_squared_deviations / self.count as f64 }
+    }
+}
+
+/// What `MCTS` does when a playout reaches a state that is already on its path, which can only
+/// happen in games whose transitions can revisit a state. See "Handling Cycles" in
+/// montecarlographsearch.md.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CyclePolicy {
+    /// End the playout at the repeat and score it as a draw. The edge that repeated counts the
+    /// visit in `Edge::repeats`, so its value keeps the draw rather than the repeated node's Q.
+    Draw,
+    /// End the playout at the repeat and score it as `-penalty` for the player who repeated,
+    /// counted on the edge like `Draw`.
+    Penalty(f64),
+    /// Give the repeated state its own node, outside `MCTS::node_ids`, so the cycle unrolls into
+    /// fresh nodes rather than looping. There is one copy per state and set of states above it,
+    /// so the cycle unrolls once per new state it passes and then repeats like `Draw`. The copy
+    /// replaces the child of the edge it was reached through, so later paths through that edge
+    /// see it too until a different set of states above swaps in another copy.
+    PathDependent
+}
+
+/// Search parameters. `MCTS::new` uses `MCTSConfig::default()`, pass your own to
+/// `MCTS::with_config` to tune them per game.
+#[derive(Debug, Clone)]
+pub struct MCTSConfig {
+    pub c_puct: f64, // exploration constant in PUCT
+    pub fpu: f64, // first-play urgency, the Q assumed for children that have never been visited
+    pub initial_edge_visits: u32, // edge visits an edge starts with when its child is rolled out during expansion
+    pub seed: u64, // seeds the evaluator's RNG and the node table hashers
+    /// Roll out every new child during expansion, rather than only the child selected after it.
+    pub rollout_new_children: bool,
+    pub backup: Backup,
+    /// MCTS-Solver: propagate proven wins, losses and draws up the graph, force proven wins and
+    /// avoid proven losses in selection, and stop playouts at proven nodes. A proven win cuts
+    /// off its siblings, so this needs utilities of 1, 0 and -1: it panics on any other utility
+    /// and can't be combined with `margin_blend`.
+    pub solver: bool,
+    pub cycles: CyclePolicy,
+    /// Keep a value per player on every node instead of one negamax Q, and have each node pick the
+    /// edge best for its own player to move (max^n). Works for any number of players and any
+    /// utilities, but always recomputes like `Backup::Idempotent` and disables the solver.
+    pub multi_player: bool,
+    /// Blend evaluations into win/loss plus margin before backing them up. Meant for games with
+    /// score utilities and evaluators that play to the end, like `RandomRollout`.
+    pub margin_blend: Option<MarginBlend>,
+    /// Mix Dirichlet noise into the root priors, for self-play. Vary `seed` between games too,
+    /// otherwise every game draws the same noise.
+    pub root_noise: Option<DirichletNoise>,
+    pub root_selection: RootSelection,
+    pub rave: Option<Rave>
+}
+
+impl Default for MCTSConfig {
+    fn default() -> Self {
+        MCTSConfig {
+            c_puct: 1.,
+            fpu: 0.,
+            initial_edge_visits: 1,
+            seed: 0,
+            rollout_new_children: true,
+            backup: Backup::Idempotent,
+            solver: false,
+            cycles: CyclePolicy::Draw,
+            multi_player: false,
+            margin_blend: None,
+            root_noise: None,
+            root_selection: RootSelection::PUCT,
+            rave: None
+        }
+    }
+}
+
+// memory_estimate walks every node, so only check it every so often
+const MEMORY_CHECK_INTERVAL: u32 = 128;
+
+/// Index of a node in `MCTS::nodes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(pub usize);
+
+/// The edge for one action out of a node. Two actions reaching the same child keep separate edges.
+#[derive(Debug, Clone)]
+pub struct Edge<A> {
+    pub action: A,
+    pub child: NodeId,
+    pub visits: u32, // N(s,a), how many times PUCT at the parent chose this edge
+    pub prior: f64, // P(s,a), 1 for every edge when the evaluator has no prior, the outcome's probability at chance nodes
+    pub amaf_visits: u32, // playouts through the parent in which its player to move played the action, for RAVE
+    pub amaf_value: f64, // sum of those playouts' rewards for the parent's player to move
+    pub repeats: u32, // visits that reached a state already on the playout's path, scored by `MCTSConfig::cycles`
+    seen_visits: u32, // visits and child Q as of the parent's last incremental backup through this edge
+    seen_Q: f64
+}
+
+impl<A> Edge<A> {
+    /// Mean AMAF reward for the parent's player to move, `None` before any.
+    pub fn amaf_Q(&self) -> Option<f64> {
+        (self.amaf_visits > 0).then(|| self.amaf_value / self.amaf_visits as f64)
+    }
+}
+
 pub struct MCTSNode<S: GameState> {
     pub game_state: Rc<S>,
     is_terminal: bool,
     is_expanded: bool,
     pub N: u32, // visit count
-    pub Q: f64, // reguralized value
-    pub child_to_edge_visits: HashMap<Rc<S>,u32,WyHash>,
-    pub results: HashMap<i32, u32> // {-1: num_losses, 0: num_draws, 1: num_wins}
+    /// Regularized value for the opponent of the player to move, i.e. the player who moved in
+    /// unless that player is moving again. Unused in multi-player mode.
+    pub Q: f64,
+    pub values: HashMap<i32, f64>, // {player: regularized value}, only kept in multi-player mode
+    pub proven: Option<Proof>, // exact value once the solver has proven it, Q is then set to match
+    pub edges: Vec<Edge<S::Action>>, // in legal action order
+    prior: Option<Vec<(S::Action, f64)>>, // from the evaluator, until the node is expanded
+    seen_edge_visits: u32, // sums of seen_visits and seen_visits * seen_Q over edges, for Backup::Incremental
+    seen_child_q_times_visits: f64,
+    pub stats: ValueStats // of the rewards backed up through the node, for its player to move
 }
 
 impl<S: GameState> MCTSNode<S> {
     pub fn new(game_state: Rc<S>) -> MCTSNode<S> {
         let terminal_result = *game_state.is_terminal();
         MCTSNode {
-            game_state: game_state,
+            game_state,
             is_terminal: terminal_result,
             is_expanded: false,
             N: 0,
             Q: 0.,
-            child_to_edge_visits: HashMap::with_hasher(WyHash::with_seed(0)),
-            results: [(-1,0),(0,0),(1,0)].into_iter().collect()
+            values: HashMap::new(),
+            proven: None,
+            edges: Vec::new(),
+            prior: None,
+            seen_edge_visits: 0,
+            seen_child_q_times_visits: 0.,
+            stats: ValueStats::default()
         }
     }
 }
 
-pub struct MCTS<G: Game> {
-    pub root: Rc<RefCell<MCTSNode<G::State>>>,
-    pub nodes: HashMap<Rc<G::State>,Rc<RefCell<MCTSNode<G::State>>>,WyHash>,
-    pub game: G
+/// The search graph lives in the `nodes` arena, edges point at their children by `NodeId`,
+/// and `node_ids` is only consulted to find transpositions when a node is expanded.
+/// Copies made by `CyclePolicy::PathDependent` are never added to `node_ids`.
+pub struct MCTS<G: Game, E: Evaluator<G> = RandomRollout> {
+    pub root: NodeId,
+    pub nodes: Vec<MCTSNode<G::State>>,
+    pub node_ids: HashMap<Rc<G::State>,NodeId,WyHash>,
+    path_copies: HashMap<(Rc<G::State>,u64),NodeId,WyHash>, // PathDependent copies by state and `history_hash`
+    pub game: G,
+    pub evaluator: E,
+    pub config: MCTSConfig,
+    rng: StdRng,
+    forced_root_edge: Option<usize>, // the root edge sequential halving is spending playouts on
+    gumbel_choice: Option<G::Action> // the action sequential halving settled on in the last search
 }
 
-impl<G: Game> MCTS<G> {
+impl<G: Game> MCTS<G, RandomRollout> {
 
     pub fn new(game: G, root_state: Rc<G::State>) -> Self {
+        MCTS::with_config(game, root_state, MCTSConfig::default())
+    }
+
+    pub fn with_config(game: G, root_state: Rc<G::State>, config: MCTSConfig) -> Self {
+        MCTS::with_evaluator(game, root_state, RandomRollout, config)
+    }
+}
+
+impl<G: Game, E: Evaluator<G>> MCTS<G, E> {
+
+    pub fn with_evaluator(game: G, root_state: Rc<G::State>, evaluator: E, config: MCTSConfig) -> Self {
+        assert!(!config.solver || config.margin_blend.is_none(), "The solver can't prove blended margins");
         let mut mcts = MCTS {
-            root: Rc::new(RefCell::new(MCTSNode::new(root_state.clone()))),
-            nodes: HashMap::with_hasher(WyHash::with_seed(0)),
-            game: game
+            root: NodeId(0),
+            nodes: Vec::new(),
+            node_ids: HashMap::with_hasher(WyHash::with_seed(config.seed)),
+            path_copies: HashMap::with_hasher(WyHash::with_seed(config.seed)),
+            game,
+            evaluator,
+            rng: StdRng::seed_from_u64(config.seed),
+            config,
+            forced_root_edge: None,
+            gumbel_choice: None
         };
         mcts.root = mcts.get_node(root_state);
         mcts
     }
 
-    pub fn get_node(&mut self, game_state: Rc<G::State>) -> Rc<RefCell<MCTSNode<G::State>>> {
-        if let Some(node) = self.nodes.get(&game_state) {
-            node.clone()
+    pub fn node(&self, id: NodeId) -> &MCTSNode<G::State> {
+        &self.nodes[id.0]
+    }
+
+    pub fn root_node(&self) -> &MCTSNode<G::State> {
+        self.node(self.root)
+    }
+
+    /// The id of the node for `game_state`, adding it to the arena if it isn't there yet.
+    pub fn get_node(&mut self, game_state: Rc<G::State>) -> NodeId {
+        if let Some(&id) = self.node_ids.get(&game_state) {
+            id
         } else {
-            let new_node = Rc::new(RefCell::new(MCTSNode::new(game_state.clone())));
-            self.nodes.insert(game_state, new_node.clone());
-            new_node
+            let id = NodeId(self.nodes.len());
+            self.nodes.push(MCTSNode::new(game_state.clone()));
+            self.node_ids.insert(game_state, id);
+            id
         }
     }
 
-    pub fn select(&mut self) -> Vec<Rc<RefCell<MCTSNode<G::State>>>> {
-        let mut path = vec![self.root.clone()];
+    /// Re-roots the search at the child reached by playing `action` from the current root.
+    pub fn advance(&mut self, action: G::Action) {
+        let root_state = self.root_node().game_state.clone();
+        let next_state = self.game.transition(root_state, action);
+        self.set_root(next_state);
+    }
+
+    /// Re-roots the search at `root_state`, keeping the statistics of the subgraph still
+    /// reachable from it and dropping every other node.
+    pub fn set_root(&mut self, root_state: Rc<G::State>) {
+        let old_root = self.root;
+        self.root = self.get_node(root_state);
+        self.gumbel_choice = None;
+        if self.root != old_root && self.nodes[self.root.0].is_expanded {
+            self.add_root_noise();
+        }
+        self.prune();
+    }
+
+    /// Compacts the arena down to the nodes reachable from the root, renumbering them.
+    fn prune(&mut self) {
+        let mut new_ids: Vec<Option<NodeId>> = vec![None; self.nodes.len()];
+        let mut reachable = vec![false; self.nodes.len()];
+        let mut stack = vec![self.root];
+        while let Some(id) = stack.pop() {
+            if reachable[id.0] {
+                continue;
+            }
+            reachable[id.0] = true;
+            stack.extend(self.nodes[id.0].edges.iter().map(|edge| edge.child));
+        }
+
+        let old_nodes = std::mem::take(&mut self.nodes);
+        for (old_index, node) in old_nodes.into_iter().enumerate() {
+            if reachable[old_index] {
+                new_ids[old_index] = Some(NodeId(self.nodes.len()));
+                self.nodes.push(node);
+            }
+        }
+        let new_id = |id: NodeId| new_ids[id.0].expect("Edge to an unreachable node?");
+        for node in &mut self.nodes {
+            for edge in &mut node.edges {
+                edge.child = new_id(edge.child);
+            }
+        }
+        self.root = new_id(self.root);
+        self.node_ids.retain(|_, id| new_ids[id.0].is_some());
+        for id in self.node_ids.values_mut() {
+            *id = new_id(*id);
+        }
+        self.path_copies.retain(|_, id| new_ids[id.0].is_some());
+        for id in self.path_copies.values_mut() {
+            *id = new_id(*id);
+        }
+    }
+
+    pub fn select(&mut self) -> Vec<NodeId> {
+        let mut path = vec![self.root];
 
         loop {
-            let last_node_rc = path.last().unwrap().clone();
-            let last_node = last_node_rc.borrow();
-            if !last_node.is_expanded || last_node.is_terminal {
+            let last_id = *path.last().unwrap();
+            let last_node = &self.nodes[last_id.0];
+            if !last_node.is_expanded || last_node.is_terminal || last_node.proven.is_some() || is_repetition(&path) {
                 break;
             }
-            drop(last_node);
 
-            let next_node_rc = self.best_child(last_node_rc.clone());
-            let next_state = next_node_rc.borrow().game_state.clone();
-            *last_node_rc.borrow_mut().child_to_edge_visits.get_mut(&next_state)
-                          .expect("No edge visit entry?") += 1;
-            path.push(next_node_rc);
+            let edge_index = self.next_edge(last_id);
+            self.nodes[last_id.0].edges[edge_index].visits += 1;
+            self.follow_edge(&mut path, edge_index);
         }
         path
     }
 
-    pub fn expand(&mut self, mut path: Vec<Rc<RefCell<MCTSNode<G::State>>>>) -> Vec<Rc<RefCell<MCTSNode<G::State>>>> {
-        let expanding_node_rc= path.last().unwrap().clone();
-        if expanding_node_rc.borrow().is_terminal {
+    /// The edge a playout takes out of `id`: PUCT's pick, or an outcome sampled by its probability
+    /// at chance nodes.
+    fn next_edge(&mut self, id: NodeId) -> usize {
+        if let Some(edge_index) = self.forced_root_edge.filter(|_| id == self.root) {
+            return edge_index;
+        }
+        let node = &self.nodes[id.0];
+        if node.game_state.chance_outcomes().is_none() {
+            return self.best_edge(node);
+        }
+        let mut remaining: f64 = self.rng.gen();
+        for (edge_index, edge) in node.edges.iter().enumerate() {
+            remaining -= edge.prior;
+            if remaining < 0. {
+                return edge_index;
+            }
+        }
+        node.edges.len() - 1
+    }
+
+    /// Pushes the child of the last node's `edge_index`th edge onto `path`. Under
+    /// `CyclePolicy::PathDependent` a child whose state is already on the path is first swapped
+    /// for its copy. A child that is still on the path is counted as a repeat of the edge.
+    fn follow_edge(&mut self, path: &mut Vec<NodeId>, edge_index: usize) {
+        let last_id = *path.last().unwrap();
+        let mut child = self.nodes[last_id.0].edges[edge_index].child;
+        if self.config.cycles == CyclePolicy::PathDependent {
+            let state = self.nodes[child.0].game_state.clone();
+            if path.iter().any(|id| self.nodes[id.0].game_state == state) {
+                child = self.path_copy(state, path);
+                self.nodes[last_id.0].edges[edge_index].child = child;
+            }
+        }
+        if path.contains(&child) {
+            // a playout through an edge expansion didn't count a visit for doesn't count as a repeat either
+            let edge = &mut self.nodes[last_id.0].edges[edge_index];
+            edge.repeats = (edge.repeats + 1).min(edge.visits);
+        }
+        path.push(child);
+    }
+
+    /// The `CyclePolicy::PathDependent` copy of `state` below the states on `path`, made the first
+    /// time that set of states repeats it.
+    fn path_copy(&mut self, state: Rc<G::State>, path: &[NodeId]) -> NodeId {
+        let key = (state, self.history_hash(path));
+        if let Some(&copy) = self.path_copies.get(&key) {
+            return copy;
+        }
+        let copy = NodeId(self.nodes.len());
+        self.nodes.push(MCTSNode::new(key.0.clone()));
+        self.path_copies.insert(key, copy);
+        copy
+    }
+
+    /// Hash of the set of states on `path`, whatever their order and however often they appear.
+    fn history_hash(&self, path: &[NodeId]) -> u64 {
+        let hashes: HashSet<u64> = path
+            .iter()
+            .map(|id| self.node_ids.hasher().hash_one(&self.nodes[id.0].game_state))
+            .collect();
+        hashes.into_iter().fold(0, u64::wrapping_add)
+    }
+
+    pub fn expand(&mut self, mut path: Vec<NodeId>) -> Vec<NodeId> {
+        let expanding_id = *path.last().unwrap();
+        let expanding_node = &self.nodes[expanding_id.0];
+        if expanding_node.is_terminal || expanding_node.proven.is_some() || is_repetition(&path) {
             return path;
         }
-        let actions = expanding_node_rc.borrow().game_state.all_legal_actions().clone().unwrap();
-        let mut child_nodes_to_backprop = Vec::new();
+        let expanding_state = self.nodes[expanding_id.0].game_state.clone();
+        let (actions, priors) = match expanding_state.chance_outcomes() {
+            Some(outcomes) => {
+                let actions: Vec<G::Action> = outcomes.iter().map(|(outcome, _)| outcome.clone()).collect();
+                let priors = edge_priors(Some(outcomes.clone()), &actions);
+                (actions, priors)
+            }
+            None => {
+                let actions = expanding_state.all_legal_actions().clone().unwrap();
+                let prior = self.node_prior(expanding_id);
+                let priors = edge_priors(prior, &actions);
+                (actions, priors)
+            }
+        };
+        let mut child_nodes_to_backprop: Vec<NodeId> = Vec::new();
 
-        {
-            let mut node_mut = expanding_node_rc.borrow_mut();
-            for action in &actions {
-                let child_state = self.game.transition(node_mut.game_state.clone(), *action);
-                node_mut.child_to_edge_visits.insert(child_state.clone(), 1);
-                let child_node_rc = self.get_node(child_state);
+        let mut edges = Vec::with_capacity(actions.len());
+        for (action, prior) in actions.into_iter().zip(priors) {
+            let child_state = self.game.transition(expanding_state.clone(), action.clone());
+            let child = self.get_node(child_state);
+            // with lazy expansion nothing is rolled out yet, so the edge has not been visited
+            let visits = if self.config.rollout_new_children { self.config.initial_edge_visits } else { 0 };
+            // initial visits to a child that is already on the path stand for repeats
+            let repeats = if self.config.cycles != CyclePolicy::PathDependent && path.contains(&child) { visits } else { 0 };
+            let mut edge = Edge { action, child, visits, prior, amaf_visits: 0, amaf_value: 0., repeats, seen_visits: visits, seen_Q: 0. };
+            edge.seen_Q = self.edge_Q(&self.nodes[expanding_id.0], &edge);
+            edges.push(edge);
 
-                // Collect child nodes that need backprop
-                if child_node_rc.borrow().N == 0 {
-                    child_nodes_to_backprop.push(child_node_rc);
-                }
+            // Collect child nodes that need backprop, once even if several actions reach them
+            if self.config.rollout_new_children && self.nodes[child.0].N == 0
+                && !child_nodes_to_backprop.contains(&child) {
+                child_nodes_to_backprop.push(child);
             }
-            node_mut.is_expanded = true;
+        }
+        let node_mut = &mut self.nodes[expanding_id.0];
+        node_mut.seen_edge_visits = edges.iter().map(|edge| edge.seen_visits).sum();
+        node_mut.seen_child_q_times_visits = edges.iter().map(|edge| edge.seen_Q * edge.seen_visits as f64).sum();
+        node_mut.edges = edges;
+        node_mut.is_expanded = true;
+        if expanding_id == self.root {
+            self.add_root_noise();
         }
 
-        for child_node_rc in child_nodes_to_backprop {
-            let reward_map = self.rollout(child_node_rc.clone());
+        for child in child_nodes_to_backprop {
             let mut temp_path = path.clone();
-            temp_path.push(child_node_rc);
+            temp_path.push(child);
+            let reward_map = self.evaluate_path(&temp_path);
             self.backprop(temp_path, reward_map);
         }
 
-        path.push(self.best_child(expanding_node_rc));
+        let edge_index = self.next_edge(expanding_id);
+        if !self.config.rollout_new_children {
+            // nothing was rolled out during expansion, so this playout is the edge's first visit
+            self.nodes[expanding_id.0].edges[edge_index].visits += 1;
+        }
+        self.follow_edge(&mut path, edge_index);
         path
     }
 
-    pub fn rollout(&mut self, node_rc: Rc<RefCell<MCTSNode<G::State>>>) -> HashMap<i32, i32> {
-        let mut rng = thread_rng();
+    /// Mixes `MCTSConfig::root_noise` into the priors of the root's edges. The noise is scaled by
+    /// the priors' total, so priors that were never normalized keep their scale.
+    fn add_root_noise(&mut self) {
+        let Some(noise) = self.config.root_noise else { return };
+        let root = &self.nodes[self.root.0];
+        if root.edges.len() < 2 || root.game_state.chance_outcomes().is_some() {
+            return;
+        }
+        let dirichlet = Dirichlet::new(&vec![noise.alpha; root.edges.len()]).expect("Bad Dirichlet alpha");
+        let sample = dirichlet.sample(&mut self.rng);
+        let edges = &mut self.nodes[self.root.0].edges;
+        let total: f64 = edges.iter().map(|edge| edge.prior).sum();
+        for (edge, eta) in edges.iter_mut().zip(sample) {
+            edge.prior = (1. - noise.epsilon) * edge.prior + noise.epsilon * eta * total;
+        }
+    }
 
-        let mut cur_state = node_rc.borrow().game_state.clone();
-        while !cur_state.is_terminal() {
-            let actions_vec = cur_state.all_legal_actions().clone().unwrap();
-            let action = *actions_vec.choose(&mut rng).unwrap();
-            cur_state = self.game.transition(cur_state, action);
+    /// Asks the evaluator for the value of the node's state, `{player: value}`, blended by
+    /// `MCTSConfig::margin_blend` if set. Any prior it returns is kept on the node for when it
+    /// gets expanded.
+    pub fn evaluate(&mut self, id: NodeId) -> HashMap<i32, f64> {
+        self.evaluation(id, false).values
+    }
+
+    fn evaluation(&mut self, id: NodeId, record_moves: bool) -> Evaluation<G::Action> {
+        let state = self.nodes[id.0].game_state.clone();
+        let mut evaluation = if record_moves {
+            self.evaluator.evaluate_recording_moves(&mut self.game, state, &mut self.rng)
+        } else {
+            self.evaluator.evaluate(&mut self.game, state, &mut self.rng)
+        };
+        if evaluation.prior.is_some() {
+            self.nodes[id.0].prior = evaluation.prior.take();
         }
+        if let Some(margin_blend) = self.config.margin_blend {
+            evaluation.values = evaluation.values.into_iter().map(|(player, value)| (player, margin_blend.blend(value))).collect();
+        }
+        evaluation
+    }
 
-        let reward_map = cur_state.result().clone().expect("No result for terminal state?").into_iter().collect();
-        reward_map
+    /// `evaluate` for the last node on `path`, recording the playout for RAVE if it's on.
+    fn evaluate_path(&mut self, path: &[NodeId]) -> HashMap<i32, f64> {
+        let evaluation = self.evaluation(*path.last().expect("Path is somehow empty"), self.config.rave.is_some());
+        if self.config.rave.is_some() {
+            self.update_amaf(path, &evaluation.moves, &evaluation.values);
+        }
+        evaluation.values
     }
 
-    pub fn backprop(&mut self, path: Vec<Rc<RefCell<MCTSNode<G::State>>>>, reward_map: HashMap<i32,i32>) {
+    /// All moves as first: every edge out of a node on `path` whose action the node's player to
+    /// move played later in the playout, further down the path or in the rollout's `moves`, is
+    /// credited with that player's reward, as if it had been played first.
+    fn update_amaf(&mut self, path: &[NodeId], moves: &[(i32, G::Action)], reward_map: &HashMap<i32, f64>) {
+        let mut played: HashMap<i32, HashSet<G::Action>> = HashMap::new();
+        for (player, action) in moves {
+            played.entry(*player).or_default().insert(action.clone());
+        }
+        for (i, &id) in path.iter().enumerate().rev() {
+            let node = &self.nodes[id.0];
+            if node.game_state.chance_outcomes().is_some() {
+                continue;
+            }
+            let player = *node.game_state.player();
+            let taken = path.get(i + 1).and_then(|&next| node.edges.iter().find(|edge| edge.child == next));
+            if let Some(edge) = taken {
+                played.entry(player).or_default().insert(edge.action.clone());
+            }
+            let Some(actions) = played.get(&player) else { continue };
+            let reward = zero_sum_reward(reward_map, player);
+            for edge in self.nodes[id.0].edges.iter_mut().filter(|edge| actions.contains(&edge.action)) {
+                edge.amaf_visits += 1;
+                edge.amaf_value += reward;
+            }
+        }
+    }
+
+    /// Nodes that were never evaluated (e.g. the root) ask the evaluator for just the prior.
+    fn node_prior(&mut self, id: NodeId) -> Option<Vec<(G::Action, f64)>> {
+        let stored = self.nodes[id.0].prior.take();
+        stored.or_else(|| {
+            let state = self.nodes[id.0].game_state.clone();
+            self.evaluator.prior(&mut self.game, state, &mut self.rng)
+        })
+    }
+
+    pub fn backprop(&mut self, path: Vec<NodeId>, reward_map: HashMap<i32,f64>) {
         if path.is_empty() {
             return;
         }
+        if self.config.multi_player {
+            return self.backprop_values(path, reward_map);
+        }
 
-        let mut reward = *reward_map.get(&path.last().expect("backpropping on empty path?")
-                                                   .borrow().game_state.player())
-                                         .expect("Reward map is broken");
-        for node_rc in path.into_iter().rev() {
-            let sum_of_child_q_times_visits: f64 = {
-                let node_borrow = node_rc.borrow();
-                node_borrow
-                    .child_to_edge_visits
-                    .iter()
-                    .map(|(child_state, &edge_visits)| {
-                        let child_node_rc = self.get_node(child_state.clone());
-                        let child_node_borrow = child_node_rc.borrow();
-                        child_node_borrow.Q * edge_visits as f64
-                    })
-                    .sum()
+        // every node takes the reward of its own player to move, so extra turns keep their sign.
+        // A repeat is left alone, its parent scores the edge into it.
+        for (i, &id) in path.iter().enumerate().rev().skip(is_repetition(&path) as usize) {
+            let reward = zero_sum_reward(&reward_map, *self.nodes[id.0].game_state.player());
+            let (N, sum_of_child_q_times_visits) = match self.config.backup {
+                Backup::Idempotent => self.recompute(id),
+                Backup::Incremental => self.refresh(id, path.get(i + 1).copied())
+            };
+            if self.config.solver {
+                self.update_proof(id);
+            }
+            let expectation = self.expected_child_Q(id);
+            let node_mut = &mut self.nodes[id.0];
+            node_mut.N = N;
+            node_mut.Q = match (node_mut.proven, expectation) {
+                (Some(proof), _) => proof.value(),
+                (None, Some(expected_child_Q)) => -expected_child_Q,
+                (None, None) => -(1./N as f64)*(reward + sum_of_child_q_times_visits)
             };
-            let mut node_mut = node_rc.borrow_mut();
-            node_mut.N = 1 + node_mut.child_to_edge_visits.values().sum::<u32>();
-            node_mut.Q = -(1./node_mut.N as f64)*(reward as f64 + sum_of_child_q_times_visits);
-            node_mut.results.entry(reward).and_modify(|n| {*n += 1});
-            reward = -1 * reward;
+            node_mut.stats.push(reward);
         }
     }
 
-    pub fn PUCT(&mut self, parent: Rc<RefCell<MCTSNode<G::State>>>, node: Rc<RefCell<MCTSNode<G::State>>>) -> f64 { 
-        let parent_borrow = parent.borrow();
-        let node_borrow = node.borrow();
-        let c_puct = 1.;
-        let N_sa = parent_borrow.child_to_edge_visits.get(&node_borrow.game_state).expect("Calling PUCT on a Node that doesn't exist?");
-        return node_borrow.Q + c_puct * 1. * f64::sqrt(parent_borrow.N as f64) / (1 + N_sa) as f64;
+    /// Multi-player backprop: every node gets each player's reward plus that player's child values
+    /// times edge visits, over N. Nothing is negated, players missing from `reward_map` get 0.
+    fn backprop_values(&mut self, path: Vec<NodeId>, reward_map: HashMap<i32,f64>) {
+        for &id in path.iter().rev().skip(is_repetition(&path) as usize) {
+            let node = &self.nodes[id.0];
+            let N = 1 + node.edges.iter().map(|edge| edge.visits).sum::<u32>();
+            let mut sums = reward_map.clone();
+            for edge in &node.edges {
+                for (&player, value) in &self.nodes[edge.child.0].values {
+                    *sums.entry(player).or_insert(0.) += value * (edge.visits - edge.repeats) as f64;
+                }
+                if edge.repeats > 0 {
+                    *sums.entry(*node.game_state.player()).or_insert(0.) += self.repeat_value() * edge.repeats as f64;
+                }
+            }
+            let reward = reward_map.get(node.game_state.player()).copied();
+            let values = match self.expected_child_values(id) {
+                Some(expected_values) => expected_values,
+                None => sums.into_iter().map(|(player, sum)| (player, sum / N as f64)).collect()
+            };
+            let node_mut = &mut self.nodes[id.0];
+            node_mut.N = N;
+            node_mut.values = values;
+            if let Some(reward) = reward {
+                node_mut.stats.push(reward);
+            }
+        }
+    }
+
+    /// At a chance node that has visited children, the probability-weighted mean of their values to
+    /// the node's player, see `child_Q`. The expectation stands in for the node's own Q.
+    fn expected_child_Q(&self, id: NodeId) -> Option<f64> {
+        let node = &self.nodes[id.0];
+        node.game_state.chance_outcomes().as_ref()?;
+        let visited = || node.edges.iter().filter(|edge| self.nodes[edge.child.0].N > 0);
+        let total: f64 = visited().map(|edge| edge.prior).sum();
+        (total > 0.).then(|| visited().map(|edge| edge.prior * self.child_Q(node, edge.child)).sum::<f64>() / total)
     }
 
-    pub fn best_child(&mut self, node: Rc<RefCell<MCTSNode<G::State>>>) -> Rc<RefCell<MCTSNode<G::State>>> {
-        let children_states: Vec<Rc<G::State>> = {
-            let node_borrow = node.borrow();
-            node_borrow.child_to_edge_visits.keys().cloned().collect()
+    /// `expected_child_Q` for every player's value in multi-player mode.
+    fn expected_child_values(&self, id: NodeId) -> Option<HashMap<i32, f64>> {
+        let node = &self.nodes[id.0];
+        node.game_state.chance_outcomes().as_ref()?;
+        let visited = || node.edges.iter().filter(|edge| self.nodes[edge.child.0].N > 0);
+        let total: f64 = visited().map(|edge| edge.prior).sum();
+        if total <= 0. {
+            return None;
+        }
+        let mut values = HashMap::new();
+        for edge in visited() {
+            for (&player, value) in &self.nodes[edge.child.0].values {
+                *values.entry(player).or_insert(0.) += edge.prior * value / total;
+            }
+        }
+        Some(values)
+    }
+
+    /// Proves a terminal node from its result, or an expanded node from its children: any child
+    /// that is a proven win for the player to move makes it a loss for their opponent,
+    /// all children proven losses make it a win, and all children proven otherwise make it a draw.
+    /// A chance node is only proven once every outcome is proven the same.
+    fn update_proof(&mut self, id: NodeId) {
+        let node = &self.nodes[id.0];
+        if node.proven.is_some() {
+            return;
+        }
+        let proof = if node.is_terminal {
+            let player = node.game_state.player();
+            let (_, utility) = node.game_state.utilities().expect("No result for terminal state?")
+                .into_iter()
+                .find(|(p, _)| p == player)
+                .expect("No result for the player to move?");
+            assert!([-1., 0., 1.].contains(&utility), "The solver needs utilities of 1, 0 or -1, got {}", utility);
+            Some(Proof::from_value(-utility))
+        } else if node.is_expanded {
+            let child_proofs: Vec<Option<Proof>> = node.edges.iter().map(|edge| self.child_proof(node, edge.child)).collect();
+            if node.game_state.chance_outcomes().is_some() {
+                // chance picks the outcome, so only outcomes that all agree prove anything
+                child_proofs.first().copied().flatten().filter(|proof| child_proofs.iter().all(|other| *other == Some(*proof))).map(Proof::flip)
+            } else if child_proofs.contains(&Some(Proof::Win)) {
+                Some(Proof::Loss)
+            } else if child_proofs.iter().all(|proof| *proof == Some(Proof::Loss)) {
+                Some(Proof::Win)
+            } else if child_proofs.iter().all(|proof| proof.is_some()) {
+                Some(Proof::Draw)
+            } else {
+                None
+            }
+        } else {
+            None
         };
+        self.nodes[id.0].proven = proof;
+    }
 
-        let best_child_state = children_states
-            .into_iter()
-            .max_by(|state_a, state_b| {
-                let child_a = self.get_node(state_a.clone());
-                let child_b = self.get_node(state_b.clone());
-                let puct_a: f64 = self.PUCT(node.clone(),child_a);
-                let puct_b: f64 = self.PUCT(node.clone(), child_b);
-                puct_a.partial_cmp(&puct_b).expect("Comparison failed due to NaN")
-            }).expect("Called best child on no children");
-        
-        self.get_node(best_child_state)
+    /// N and the sum of edge Q times edge visits, from every child's current Q.
+    fn recompute(&self, id: NodeId) -> (u32, f64) {
+        let node = &self.nodes[id.0];
+        let sum_of_child_q_times_visits: f64 = node
+            .edges
+            .iter()
+            .map(|edge| self.edge_Q(node, edge) * edge.visits as f64)
+            .sum();
+        (1 + node.edges.iter().map(|edge| edge.visits).sum::<u32>(), sum_of_child_q_times_visits)
+    }
+
+    /// N and the sum of edge Q times edge visits, updating the running sums with the deltas of
+    /// the edges to `next`, the child the playout went through.
+    fn refresh(&mut self, id: NodeId, next: Option<NodeId>) -> (u32, f64) {
+        if let Some(next) = next {
+            let node = &self.nodes[id.0];
+            let next_Qs: Vec<(usize, f64)> = node.edges
+                .iter()
+                .enumerate()
+                .filter(|(_, edge)| edge.child == next)
+                .map(|(edge_index, edge)| (edge_index, self.edge_Q(node, edge)))
+                .collect();
+            let node = &mut self.nodes[id.0];
+            for (edge_index, next_Q) in next_Qs {
+                let edge = &mut node.edges[edge_index];
+                node.seen_edge_visits = node.seen_edge_visits + edge.visits - edge.seen_visits;
+                node.seen_child_q_times_visits += edge.visits as f64 * next_Q - edge.seen_visits as f64 * edge.seen_Q;
+                edge.seen_visits = edge.visits;
+                edge.seen_Q = next_Q;
+            }
+        }
+        let node = &self.nodes[id.0];
+        (1 + node.seen_edge_visits, node.seen_child_q_times_visits)
+    }
+
+    /// Value of `child` to the player to move at `parent`: the child's Q, negated if that player
+    /// moves again at the child, or in multi-player mode that player's entry in the child's values.
+    pub fn child_Q(&self, parent: &MCTSNode<G::State>, child: NodeId) -> f64 {
+        let child_node = &self.nodes[child.0];
+        if self.config.multi_player {
+            child_node.values.get(parent.game_state.player()).copied().unwrap_or(0.)
+        } else if self.moves_again(parent, child) {
+            -child_node.Q
+        } else {
+            child_node.Q
+        }
+    }
+
+    /// Value of `edge` to the player to move at `parent`: its child's Q, see `child_Q`, with the
+    /// visits that repeated a state scored by `MCTSConfig::cycles` instead.
+    pub fn edge_Q(&self, parent: &MCTSNode<G::State>, edge: &Edge<G::Action>) -> f64 {
+        let child_Q = self.child_Q(parent, edge.child);
+        if edge.repeats == 0 {
+            return child_Q;
+        }
+        let repeats = edge.repeats as f64;
+        (child_Q * (edge.visits as f64 - repeats) + self.repeat_value() * repeats) / edge.visits as f64
+    }
+
+    /// What a repeat is worth to the player who repeated.
+    fn repeat_value(&self) -> f64 {
+        match self.config.cycles {
+            CyclePolicy::Penalty(penalty) => -penalty,
+            _ => 0.
+        }
+    }
+
+    /// The child's proof for the player to move at `parent`, see `child_Q`.
+    fn child_proof(&self, parent: &MCTSNode<G::State>, child: NodeId) -> Option<Proof> {
+        let proof = self.nodes[child.0].proven;
+        if self.moves_again(parent, child) { proof.map(Proof::flip) } else { proof }
+    }
+
+    /// Whether the player to move at `parent` is also the one to move at `child`, e.g. an extra turn.
+    fn moves_again(&self, parent: &MCTSNode<G::State>, child: NodeId) -> bool {
+        self.nodes[child.0].game_state.player() == parent.game_state.player()
+    }
+
+    pub fn PUCT(&self, parent: &MCTSNode<G::State>, edge: &Edge<G::Action>) -> f64 {
+        let child = &self.nodes[edge.child.0];
+        let Q = if child.N == 0 { self.config.fpu } else { self.edge_Q(parent, edge) };
+        let Q = match (self.config.rave, edge.amaf_Q()) {
+            (Some(rave), Some(amaf_Q)) => {
+                let beta = rave.beta(edge.visits);
+                (1. - beta) * Q + beta * amaf_Q
+            }
+            _ => Q
+        };
+        Q + self.config.c_puct * edge.prior * f64::sqrt(parent.N as f64) / (1 + edge.visits) as f64
+    }
+
+    /// Index into `node.edges` of the edge PUCT picks. The solver forces proven wins and only
+    /// picks proven losses when there is nothing else.
+    fn best_edge(&self, node: &MCTSNode<G::State>) -> usize {
+        node.edges
+            .iter()
+            .map(|edge| match self.child_proof(node, edge.child) {
+                Some(Proof::Win) => f64::INFINITY,
+                Some(Proof::Loss) => f64::NEG_INFINITY,
+                _ => self.PUCT(node, edge)
+            })
+            .enumerate()
+            .max_by(|(_, puct_a), (_, puct_b)| puct_a.partial_cmp(puct_b).expect("Comparison failed due to NaN"))
+            .expect("Called best child on no children")
+            .0
+    }
+
+    /// The action PUCT picks at `node` and the child it leads to.
+    pub fn best_child(&self, node: NodeId) -> (G::Action, NodeId) {
+        let node = &self.nodes[node.0];
+        let edge = &node.edges[self.best_edge(node)];
+        (edge.action.clone(), edge.child)
     }
 
     pub fn run(&mut self) {
         let mut path = self.select();
         path = self.expand(path);
-        let reward = self.rollout(path.last().expect("Path is somehow empty").clone());
+        if is_repetition(&path) {
+            // the repeat itself isn't updated, its parent is scored for the move that repeated
+            let repeater = *self.nodes[path[path.len() - 2].0].game_state.player();
+            let value = self.repeat_value();
+            self.backprop(path, [(repeater, value)].into_iter().collect());
+            return;
+        }
+        let leaf = *path.last().expect("Path is somehow empty");
+        let reward = match self.nodes[leaf.0].proven {
+            // no need to evaluate a node whose value is known
+            Some(proof) => [(*self.nodes[leaf.0].game_state.player(), -proof.value())].into_iter().collect(),
+            None => self.evaluate_path(&path)
+        };
         self.backprop(path, reward);
     }
 
     pub fn search(&mut self, n: u32) {
-      for _ in 0..n { self.run() }  
+        match self.config.root_selection {
+            RootSelection::PUCT => for _ in 0..n { self.run() },
+            RootSelection::Gumbel { considered_actions, c_visit, c_scale } => self.sequential_halving(n, considered_actions, c_visit, c_scale)
+        }
     }
-}
\ No newline at end of file
+
+    /// Gumbel-Top-k over the root prior, then sequential halving of `n` playouts between the
+    /// sampled actions, keeping the better half by `gumbel + log prior + sigma(Q)` after each phase.
+    fn sequential_halving(&mut self, n: u32, considered_actions: usize, c_visit: f64, c_scale: f64) {
+        let mut playouts = 0;
+        if !self.root_node().is_expanded && n > 0 {
+            self.run();
+            playouts += 1;
+        }
+        let n_edges = self.root_node().edges.len();
+        if n_edges == 0 || self.root_node().game_state.chance_outcomes().is_some() {
+            for _ in playouts..n { self.run() }
+            return;
+        }
+        let gumbel = Gumbel::new(0., 1.).expect("Bad Gumbel distribution");
+        let logits = self.root_logits();
+        let gumbels: Vec<f64> = (0..n_edges).map(|_| gumbel.sample(&mut self.rng)).collect();
+        // best first by gumbel + log prior + the given sigmas
+        let rank = |remaining: &mut Vec<usize>, sigmas: &[f64]| {
+            let score = |edge_index: usize| gumbels[edge_index] + logits[edge_index] + sigmas[edge_index];
+            remaining.sort_by(|&a, &b| score(b).partial_cmp(&score(a)).expect("Comparison failed due to NaN"));
+        };
+
+        let mut remaining: Vec<usize> = (0..n_edges).collect();
+        rank(&mut remaining, &vec![0.; n_edges]);
+        remaining.truncate(considered_actions.clamp(1, n_edges));
+        let phases = (remaining.len() as f64).log2().ceil().max(1.) as u32;
+        while playouts < n {
+            let per_action = (n / (phases * remaining.len() as u32)).max(1);
+            for &edge_index in &remaining {
+                self.forced_root_edge = Some(edge_index);
+                for _ in 0..per_action.min(n - playouts) {
+                    self.run();
+                    playouts += 1;
+                }
+            }
+            self.forced_root_edge = None;
+            rank(&mut remaining, &self.sigma_completed_Q(c_visit, c_scale));
+            remaining.truncate(remaining.len().div_ceil(2));
+        }
+        let winner = remaining[0];
+        self.gumbel_choice = Some(self.root_node().edges[winner].action.clone());
+    }
+
+    /// Log of the root edges' priors, normalized over the edges.
+    fn root_logits(&self) -> Vec<f64> {
+        let edges = &self.root_node().edges;
+        let total: f64 = edges.iter().map(|edge| edge.prior).sum();
+        edges.iter().map(|edge| (edge.prior / total).ln()).collect()
+    }
+
+    /// sigma of every root edge's completed Q: the child Q for the root player where the child
+    /// was visited, the root's own value otherwise. Qs are min-max normalized over the edges.
+    fn sigma_completed_Q(&self, c_visit: f64, c_scale: f64) -> Vec<f64> {
+        let root = self.root_node();
+        let root_value = if self.config.multi_player {
+            root.values.get(root.game_state.player()).copied().unwrap_or(0.)
+        } else {
+            -root.Q
+        };
+        let completed: Vec<f64> = root.edges
+            .iter()
+            .map(|edge| if self.nodes[edge.child.0].N > 0 { self.edge_Q(root, edge) } else { root_value })
+            .collect();
+        let min = completed.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = completed.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let max_visits = root.edges.iter().map(|edge| edge.visits).max().unwrap_or(0) as f64;
+        completed
+            .into_iter()
+            .map(|q| {
+                let normalized = if max > min { (q - min) / (max - min) } else { 0.5 };
+                (c_visit + max_visits) * c_scale * normalized
+            })
+            .collect()
+    }
+
+    /// The action the last sequential halving search settled on, `None` before one has run.
+    pub fn gumbel_action(&self) -> Option<G::Action> {
+        self.gumbel_choice.clone()
+    }
+
+    /// The Gumbel AlphaZero policy target: softmax over the root edges of log prior plus sigma of
+    /// the completed Q, with the `c_visit` and `c_scale` of `RootSelection::Gumbel`. Works after
+    /// a PUCT search too.
+    pub fn completed_q_policy(&self, c_visit: f64, c_scale: f64) -> Vec<(G::Action, f64)> {
+        let logits = self.root_logits();
+        let sigmas = self.sigma_completed_Q(c_visit, c_scale);
+        let scores: Vec<f64> = logits.iter().zip(&sigmas).map(|(logit, sigma)| logit + sigma).collect();
+        let max = scores.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let weights: Vec<f64> = scores.iter().map(|score| (score - max).exp()).collect();
+        let total: f64 = weights.iter().sum();
+        self.root_node()
+            .edges
+            .iter()
+            .zip(weights)
+            .map(|(edge, weight)| (edge.action.clone(), weight / total))
+            .collect()
+    }
+
+    /// Runs playouts until one of `limits` is hit and returns how many were run. Limits that
+    /// aren't bounded would never stop, so those run nothing and return 0. Panics under
+    /// `RootSelection::Gumbel`, which has to know its budget up front, use `search` for it.
+    pub fn search_with_limits(&mut self, limits: &SearchLimits) -> u32 {
+        assert!(self.config.root_selection == RootSelection::PUCT, "Gumbel root selection needs a fixed budget, call search");
+        if !limits.is_bounded() {
+            return 0;
+        }
+        let start = Instant::now();
+        let mut playouts = 0;
+        loop {
+            if limits.playouts.is_some_and(|max| playouts >= max)
+                || limits.time.is_some_and(|time| start.elapsed() >= time)
+                || limits.max_nodes.is_some_and(|max| self.nodes.len() >= max)
+                || (playouts % MEMORY_CHECK_INTERVAL == 0
+                    && limits.max_memory.is_some_and(|max| self.memory_estimate() >= max))
+                || (limits.early_stop && self.root_node().proven.is_some())
+                || (limits.early_stop && self.is_decided(self.remaining_playouts(limits, playouts, start.elapsed())))
+            {
+                break;
+            }
+            self.run();
+            playouts += 1;
+        }
+        playouts
+    }
+
+    /// Upper bound on the playouts left in the budget, extrapolating the time limit from the
+    /// rate seen so far.
+    fn remaining_playouts(&self, limits: &SearchLimits, playouts: u32, elapsed: Duration) -> Option<u32> {
+        let by_count = limits.playouts.map(|max| max.saturating_sub(playouts));
+        let by_time = limits.time.filter(|_| playouts > 0).map(|time| {
+            let per_playout = elapsed.as_secs_f64() / playouts as f64;
+            (time.saturating_sub(elapsed).as_secs_f64() / per_playout).ceil() as u32
+        });
+        match (by_count, by_time) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b)
+        }
+    }
+
+    /// Whether the most visited root edge leads the runner-up by more than `remaining` visits.
+    fn is_decided(&self, remaining: Option<u32>) -> bool {
+        let Some(remaining) = remaining else { return false };
+        let root = self.root_node();
+        let (mut best, mut second) = (0, 0);
+        for edge in &root.edges {
+            if edge.visits > best {
+                second = best;
+                best = edge.visits;
+            } else if edge.visits > second {
+                second = edge.visits;
+            }
+        }
+        root.edges.len() > 1 && best - second > remaining
+    }
+
+    /// Rough size in bytes of the search graph: the arena, the transposition table, the states
+    /// and the edges. Heap data owned by the states themselves is not counted.
+    pub fn memory_estimate(&self) -> usize {
+        let per_node = size_of::<MCTSNode<G::State>>()
+            + size_of::<G::State>()
+            + 2 * size_of::<usize>(); // Rc counts
+        let per_table_entry = size_of::<(Rc<G::State>, NodeId)>() + 1; // + hashbrown control byte
+        let per_copy_entry = size_of::<((Rc<G::State>, u64), NodeId)>() + 1;
+        let edges: usize = self.nodes.iter().map(|node| node.edges.capacity()).sum();
+        self.nodes.capacity() * per_node
+            + self.node_ids.capacity() * per_table_entry
+            + self.path_copies.capacity() * per_copy_entry
+            + edges * size_of::<Edge<G::Action>>()
+    }
+
+    /// `(action, edge visits, child Q)` for every edge out of the root, in legal action order,
+    /// with child Q as in `edge_Q`. Empty until the root has been expanded.
+    pub fn root_stats(&self) -> Vec<ActionStats<G::Action>> {
+        let root = self.root_node();
+        root.edges
+            .iter()
+            .map(|edge| (edge.action.clone(), edge.visits, self.edge_Q(root, edge)))
+            .collect()
+    }
+
+    /// The move to play from the root according to `rule`, or `None` if the root has no children.
+    /// A move the solver has proven to win is always preferred.
+    pub fn best_action(&self, rule: MoveSelection) -> Option<G::Action> {
+        let root = self.root_node();
+        let proven_win = root.edges
+            .iter()
+            .find(|edge| self.child_proof(root, edge.child) == Some(Proof::Win));
+        match proven_win {
+            Some(edge) => Some(edge.action.clone()),
+            None => select_action(&self.root_stats(), rule)
+        }
+    }
+
+    /// The root policy as `(action, probability)` pairs, proportional to `N(s,a)^(1/temperature)`.
+    /// A temperature of 0 splits all the mass evenly between the most visited actions.
+    pub fn policy(&self, temperature: f64) -> Vec<(G::Action, f64)> {
+        visit_policy(&self.root_stats(), temperature)
+    }
+
+    /// Draws the move to play from `policy` at the schedule's temperature for `move_number`, for
+    /// diverse self-play games. `None` if the root has no edges.
+    pub fn sample_action(&mut self, move_number: u32, schedule: &TemperatureSchedule) -> Option<G::Action> {
+        let policy = self.policy(schedule.temperature(move_number));
+        policy.choose_weighted(&mut self.rng, |(_, probability)| *probability).ok().map(|(action, _)| action.clone())
+    }
+}
+
+/// The reward for `player` in a two-player zero-sum reward map, which may only hold the other
+/// player's reward.
+pub(crate) fn zero_sum_reward(reward_map: &HashMap<i32,f64>, player: i32) -> f64 {
+    match reward_map.get(&player) {
+        Some(&reward) => reward,
+        None => -*reward_map.values().next().expect("Reward map is broken")
+    }
+}
+
+/// Whether the last node on `path` already appears earlier on it.
+fn is_repetition(path: &[NodeId]) -> bool {
+    let (last, rest) = path.split_last().expect("Path is somehow empty");
+    rest.contains(last)
+}
+
+/// Picks from root statistics according to `rule`.
+pub(crate) fn select_action<A: Clone>(stats: &[ActionStats<A>], rule: MoveSelection) -> Option<A> {
+    let score = |&(_, edge_visits, q): &ActionStats<A>| match rule {
+        MoveSelection::MaxVisits => edge_visits as f64,
+        MoveSelection::MaxQ => q,
+        MoveSelection::Secure(_) if edge_visits == 0 => f64::NEG_INFINITY,
+        MoveSelection::Secure(c) => q - c / f64::sqrt(edge_visits as f64),
+    };
+    stats
+        .iter()
+        .max_by(|a, b| score(a).partial_cmp(&score(b)).expect("Comparison failed due to NaN"))
+        .map(|(action, _, _)| action.clone())
+}
+
+/// Visit count policy over root statistics, see `MCTS::policy`.
+pub(crate) fn visit_policy<A: Clone>(stats: &[ActionStats<A>], temperature: f64) -> Vec<(A, f64)> {
+    let max_visits = stats.iter().map(|&(_, edge_visits, _)| edge_visits).max().unwrap_or(0) as f64;
+    let weights: Vec<f64> = stats
+        .iter()
+        .map(|&(_, edge_visits, _)| {
+            if temperature <= 0. {
+                if edge_visits as f64 == max_visits { 1. } else { 0. }
+            } else {
+                // scale by the max first so small temperatures don't overflow
+                (edge_visits as f64 / max_visits.max(1.)).powf(1. / temperature)
+            }
+        })
+        .collect();
+    let total: f64 = weights.iter().sum();
+    let n_actions = stats.len() as f64;
+    stats
+        .iter()
+        .zip(weights)
+        .map(|((action, _, _), weight)| {
+            if total > 0. { (action.clone(), weight / total) } else { (action.clone(), 1. / n_actions) }
+        })
+        .collect()
+}
+
+/// P(s,a) for each of `actions` from an evaluator's prior, normalized over them.
+/// Without a prior every edge gets 1.
+pub(crate) fn edge_priors<A: PartialEq>(prior: Option<Vec<(A, f64)>>, actions: &[A]) -> Vec<f64> {
+    let Some(prior) = prior else {
+        return vec![1.; actions.len()];
+    };
+    let priors: Vec<f64> = actions
+        .iter()
+        .map(|action| prior.iter().find(|(a, _)| a == action).map_or(0., |(_, p)| *p))
+        .collect();
+    let total: f64 = priors.iter().sum();
+    if total > 0. {
+        priors.into_iter().map(|p| p / total).collect()
+    } else {
+        vec![1. / actions.len() as f64; actions.len()]
+    }
+}
@@ -0,0 +1,446 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::{Arc,Mutex,RwLock};
+use std::sync::atomic::{AtomicU32,AtomicU64,Ordering};
+use std::thread;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use wyhash2::WyHash;
+use crate::evaluator::Evaluator;
+use crate::game::{Game,GameState};
+use crate::mcts::{ActionStats,MCTS,MCTSConfig,MoveSelection,RootSelection,SearchLimits,edge_priors,select_action,visit_policy,zero_sum_reward};
+
+pub type SharedNodeRef<G> = Arc<Mutex<SharedNode<G>>>;
+
+/// Settings specific to `ParallelMCTS`, the rest comes from `MCTSConfig`.
+#[derive(Debug, Clone)]
+pub struct ParallelConfig {
+    pub threads: usize,
+    /// Losses a node is charged for each worker whose playout is in flight through it,
+    /// so that workers spread out over the graph instead of all following the same path.
+    pub virtual_loss: u32
+}
+
+impl Default for ParallelConfig {
+    fn default() -> Self {
+        ParallelConfig {
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            virtual_loss: 1
+        }
+    }
+}
+
+pub struct SharedEdge<G: Game> {
+    pub action: G::Action,
+    pub child: SharedNodeRef<G>,
+    pub visits: u32, // N(s,a)
+    pub prior: f64 // P(s,a)
+}
+
+/// Node of the graph shared by every worker. Boards stand in for the game states, which are
+/// `Rc`s owned by each worker's own `Game`.
+pub struct SharedNode<G: Game> {
+    pub board: G::Board,
+    player: i32, // to move
+    is_terminal: bool,
+    is_expanded: bool,
+    pub N: u32, // visit count
+    pub Q: f64, // regularized value
+    pub virtual_losses: u32, // playouts currently in flight through this node
+    pub edges: Vec<SharedEdge<G>>,
+    prior: Option<Vec<(G::Action, f64)>>
+}
+
+impl<G: Game> SharedNode<G> {
+    fn new(state: &G::State) -> SharedNode<G> {
+        SharedNode {
+            board: state.state().clone(),
+            player: *state.player(),
+            is_terminal: *state.is_terminal(),
+            is_expanded: false,
+            N: 0,
+            Q: 0.,
+            virtual_losses: 0,
+            edges: Vec::new(),
+            prior: None
+        }
+    }
+
+    /// Q as seen by `player` moving into this node, negated if `player` moves again here.
+    fn Q_for(&self, player: i32) -> f64 {
+        if self.player == player { -self.Q } else { self.Q }
+    }
+}
+
+/// Tree/graph-parallel MCTS: worker threads run playouts against one shared node table,
+/// using virtual loss so concurrent playouts diverge. Each worker builds its own `Game`
+/// with `make_game` and gets its own clone of the evaluator.
+/// Workers back up plain Q values like `Backup::Idempotent`; the solver, cycle policies,
+/// multi-player mode, chance nodes, margin blending, root noise, Gumbel root selection and RAVE
+/// are only supported by `MCTS` (and all but Gumbel by `root_parallel_search`).
+pub struct ParallelMCTS<G: Game, F, E> {
+    pub root: SharedNodeRef<G>,
+    pub nodes: RwLock<HashMap<G::Board,SharedNodeRef<G>,WyHash>>,
+    pub config: MCTSConfig,
+    pub parallel: ParallelConfig,
+    make_game: F,
+    evaluator: E,
+    searches: AtomicU64, // so every call to search seeds its workers differently
+    _game: PhantomData<fn() -> G>
+}
+
+impl<G, F, E> ParallelMCTS<G, F, E>
+where
+    G: Game,
+    G::Action: Send,
+    G::Board: Send + Sync,
+    F: Fn() -> G + Sync,
+    E: Evaluator<G> + Clone + Send + Sync
+{
+    pub fn new(make_game: F, root_board: G::Board, evaluator: E, config: MCTSConfig, parallel: ParallelConfig) -> Self {
+        let root = Arc::new(Mutex::new(SharedNode::new(&*make_game().get_state(&root_board))));
+        let mut nodes = HashMap::with_hasher(WyHash::with_seed(config.seed));
+        nodes.insert(root_board, root.clone());
+        ParallelMCTS {
+            root,
+            nodes: RwLock::new(nodes),
+            config,
+            parallel,
+            make_game,
+            evaluator,
+            searches: AtomicU64::new(0),
+            _game: PhantomData
+        }
+    }
+
+    /// Runs `n` playouts spread over `parallel.threads` workers.
+    pub fn search(&self, n: u32) {
+        let started = AtomicU32::new(0);
+        let search_index = self.searches.fetch_add(1, Ordering::Relaxed);
+        thread::scope(|scope| {
+            for thread_index in 0..self.parallel.threads.max(1) {
+                let started = &started;
+                let seed = self.config.seed
+                    .wrapping_add(search_index.wrapping_mul(self.parallel.threads as u64))
+                    .wrapping_add(thread_index as u64);
+                scope.spawn(move || {
+                    let mut worker = Worker {
+                        mcts: self,
+                        game: (self.make_game)(),
+                        evaluator: self.evaluator.clone(),
+                        rng: StdRng::seed_from_u64(seed)
+                    };
+                    while started.fetch_add(1, Ordering::Relaxed) < n {
+                        worker.run();
+                    }
+                });
+            }
+        });
+    }
+
+    /// `(action, edge visits, child Q)` for every edge out of the root, in legal action order.
+    pub fn root_stats(&self) -> Vec<ActionStats<G::Action>> {
+        let root = self.root.lock().unwrap();
+        root.edges
+            .iter()
+            .map(|edge| (edge.action.clone(), edge.visits, edge.child.lock().unwrap().Q_for(root.player)))
+            .collect()
+    }
+
+    /// See `MCTS::best_action`.
+    pub fn best_action(&self, rule: MoveSelection) -> Option<G::Action> {
+        select_action(&self.root_stats(), rule)
+    }
+
+    /// See `MCTS::policy`.
+    pub fn policy(&self, temperature: f64) -> Vec<(G::Action, f64)> {
+        visit_policy(&self.root_stats(), temperature)
+    }
+
+    fn get_node(&self, state: &G::State) -> SharedNodeRef<G> {
+        if let Some(node) = self.nodes.read().unwrap().get(state.state()) {
+            return node.clone();
+        }
+        self.nodes
+            .write().unwrap()
+            .entry(state.state().clone())
+            .or_insert_with(|| Arc::new(Mutex::new(SharedNode::new(state))))
+            .clone()
+    }
+}
+
+/// One thread's view of a `ParallelMCTS`.
+struct Worker<'a, G: Game, F, E> {
+    mcts: &'a ParallelMCTS<G, F, E>,
+    game: G,
+    evaluator: E,
+    rng: StdRng
+}
+
+impl<G, F, E> Worker<'_, G, F, E>
+where
+    G: Game,
+    G::Action: Send,
+    G::Board: Send + Sync,
+    F: Fn() -> G + Sync,
+    E: Evaluator<G> + Clone + Send + Sync
+{
+    fn run(&mut self) {
+        let mut path = self.select();
+        path = self.expand(path);
+        let leaf = path.last().expect("Path is somehow empty").clone();
+        let reward = self.evaluate(&leaf);
+        self.backprop(&path, reward);
+        for node in &path[1..] {
+            node.lock().unwrap().virtual_losses -= 1;
+        }
+    }
+
+    /// Walks down by PUCT, adding an edge visit and a virtual loss for every step.
+    fn select(&mut self) -> Vec<SharedNodeRef<G>> {
+        let mut path = vec![self.mcts.root.clone()];
+        loop {
+            let last = path.last().unwrap().clone();
+            let mut last_node = last.lock().unwrap();
+            if !last_node.is_expanded || last_node.is_terminal {
+                break;
+            }
+            let next = self.visit_best_edge(&mut last_node);
+            drop(last_node);
+            path.push(next);
+        }
+        path
+    }
+
+    fn expand(&mut self, mut path: Vec<SharedNodeRef<G>>) -> Vec<SharedNodeRef<G>> {
+        let expanding = path.last().unwrap().clone();
+        let state = {
+            let node = expanding.lock().unwrap();
+            if node.is_terminal {
+                return path;
+            }
+            self.game.get_state(&node.board)
+        };
+
+        let actions = state.all_legal_actions().clone().unwrap();
+        // don't hold the node while the evaluator runs
+        let stored_prior = expanding.lock().unwrap().prior.take();
+        let prior = stored_prior.or_else(|| self.evaluator.prior(&mut self.game, state.clone(), &mut self.rng));
+        let priors = edge_priors(prior, &actions);
+        let mut edges = Vec::with_capacity(actions.len());
+        let mut children_to_backprop: Vec<(_, Rc<G::State>)> = Vec::new();
+        for (action, prior) in actions.into_iter().zip(priors) {
+            let child_state = self.game.transition(state.clone(), action.clone());
+            let child = self.mcts.get_node(&child_state);
+            if self.mcts.config.rollout_new_children && child.lock().unwrap().N == 0
+                && !children_to_backprop.iter().any(|(node, _)| Arc::ptr_eq(node, &child)) {
+                children_to_backprop.push((child.clone(), child_state));
+            }
+            let visits = if self.mcts.config.rollout_new_children { self.mcts.config.initial_edge_visits } else { 0 };
+            edges.push(SharedEdge { action, child, visits, prior });
+        }
+
+        {
+            let mut node = expanding.lock().unwrap();
+            if node.is_expanded {
+                // another worker got here first, just carry on down its edges
+                let next = self.visit_best_edge(&mut node);
+                drop(node);
+                path.push(next);
+                return path;
+            }
+            node.edges = edges;
+            node.is_expanded = true;
+        }
+
+        for (child, child_state) in children_to_backprop {
+            let reward = self.evaluate_state(&child, child_state);
+            let mut temp_path = path.clone();
+            temp_path.push(child);
+            self.backprop(&temp_path, reward);
+        }
+
+        let mut node = expanding.lock().unwrap();
+        let next = if self.mcts.config.rollout_new_children {
+            let edge_index = self.best_edge(&node);
+            let child = node.edges[edge_index].child.clone();
+            child.lock().unwrap().virtual_losses += 1;
+            child
+        } else {
+            // nothing was rolled out during expansion, so this playout is the edge's first visit
+            self.visit_best_edge(&mut node)
+        };
+        drop(node);
+        path.push(next);
+        path
+    }
+
+    fn evaluate(&mut self, node: &SharedNodeRef<G>) -> HashMap<i32, f64> {
+        let board = node.lock().unwrap().board.clone();
+        let state = self.game.get_state(&board);
+        self.evaluate_state(node, state)
+    }
+
+    fn evaluate_state(&mut self, node: &SharedNodeRef<G>, state: Rc<G::State>) -> HashMap<i32, f64> {
+        let evaluation = self.evaluator.evaluate(&mut self.game, state, &mut self.rng);
+        if evaluation.prior.is_some() {
+            node.lock().unwrap().prior = evaluation.prior;
+        }
+        evaluation.values
+    }
+
+    /// Same update as `MCTS::backprop`. Child Qs are read one lock at a time, so a node's Q
+    /// can be computed from children another worker is halfway through updating.
+    fn backprop(&mut self, path: &[SharedNodeRef<G>], reward_map: HashMap<i32, f64>) {
+        for node in path.iter().rev() {
+            let (player, edges): (i32, Vec<(SharedNodeRef<G>, u32)>) = {
+                let node = node.lock().unwrap();
+                (node.player, node.edges.iter().map(|edge| (edge.child.clone(), edge.visits)).collect())
+            };
+            let sum_of_child_q_times_visits: f64 = edges
+                .iter()
+                .map(|(child, edge_visits)| child.lock().unwrap().Q_for(player) * *edge_visits as f64)
+                .sum();
+            let reward = zero_sum_reward(&reward_map, player);
+            let mut node_mut = node.lock().unwrap();
+            node_mut.N = 1 + node_mut.edges.iter().map(|edge| edge.visits).sum::<u32>();
+            node_mut.Q = -(1. / node_mut.N as f64) * (reward + sum_of_child_q_times_visits);
+        }
+    }
+
+    /// PUCT with every in-flight playout through the child counted as a loss for the parent's mover.
+    fn PUCT(&self, parent: &SharedNode<G>, edge: &SharedEdge<G>) -> f64 {
+        let (N, Q, virtual_losses) = {
+            let child = edge.child.lock().unwrap();
+            (child.N, child.Q_for(parent.player), child.virtual_losses * self.mcts.parallel.virtual_loss)
+        };
+        let Q = if N == 0 && virtual_losses == 0 {
+            self.mcts.config.fpu
+        } else if N == 0 {
+            -1.
+        } else {
+            (Q * N as f64 - virtual_losses as f64) / (N + virtual_losses) as f64
+        };
+        Q + self.mcts.config.c_puct * edge.prior * f64::sqrt(parent.N as f64) / (1 + edge.visits) as f64
+    }
+
+    fn best_edge(&self, node: &SharedNode<G>) -> usize {
+        node.edges
+            .iter()
+            .map(|edge| self.PUCT(node, edge))
+            .enumerate()
+            .max_by(|(_, puct_a), (_, puct_b)| puct_a.partial_cmp(puct_b).expect("Comparison failed due to NaN"))
+            .expect("Called best child on no children")
+            .0
+    }
+
+    /// Picks the PUCT edge of `node`, whose lock the caller holds, and charges it a visit and
+    /// its child a virtual loss.
+    fn visit_best_edge(&self, node: &mut SharedNode<G>) -> SharedNodeRef<G> {
+        let edge_index = self.best_edge(node);
+        let edge = &mut node.edges[edge_index];
+        edge.visits += 1;
+        edge.child.lock().unwrap().virtual_losses += 1;
+        edge.child.clone()
+    }
+}
+
+/// Root statistics for one action, merged over the workers of `root_parallel_search`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergedActionStats<A> {
+    pub action: A,
+    pub visits: u32, // edge visits summed over workers
+    pub Q: f64, // child Q averaged over workers, weighted by their edge visits
+    pub Q_std: f64 // standard deviation of the workers' child Qs
+}
+
+/// Result of `root_parallel_search`: every worker's root statistics and their merge.
+#[derive(Debug, Clone)]
+pub struct RootParallelStats<A> {
+    pub workers: Vec<Vec<ActionStats<A>>>, // each worker's MCTS::root_stats
+    pub merged: Vec<MergedActionStats<A>> // in legal action order
+}
+
+impl<A: Clone + PartialEq> RootParallelStats<A> {
+    fn merge(workers: Vec<Vec<ActionStats<A>>>) -> Self {
+        let mut merged: Vec<MergedActionStats<A>> = Vec::new();
+        let mut worker_Qs: Vec<Vec<f64>> = Vec::new();
+        for (action, visits, Q) in workers.iter().flatten() {
+            let (visits, Q) = (*visits, *Q);
+            let index = match merged.iter().position(|stats| stats.action == *action) {
+                Some(index) => index,
+                None => {
+                    merged.push(MergedActionStats { action: action.clone(), visits: 0, Q: 0., Q_std: 0. });
+                    worker_Qs.push(Vec::new());
+                    merged.len() - 1
+                }
+            };
+            // accumulate the visit-weighted sum in Q and divide it out below
+            merged[index].visits += visits;
+            merged[index].Q += Q * visits as f64;
+            worker_Qs[index].push(Q);
+        }
+        for (stats, Qs) in merged.iter_mut().zip(worker_Qs) {
+            stats.Q = if stats.visits > 0 { stats.Q / stats.visits as f64 } else { Qs.iter().sum::<f64>() / Qs.len() as f64 };
+            let mean = Qs.iter().sum::<f64>() / Qs.len() as f64;
+            stats.Q_std = (Qs.iter().map(|Q| (Q - mean).powi(2)).sum::<f64>() / Qs.len() as f64).sqrt();
+        }
+        RootParallelStats { workers, merged }
+    }
+
+    /// `(action, edge visits, child Q)` of the merged statistics, like `MCTS::root_stats`.
+    pub fn root_stats(&self) -> Vec<ActionStats<A>> {
+        self.merged.iter().map(|stats| (stats.action.clone(), stats.visits, stats.Q)).collect()
+    }
+
+    /// See `MCTS::best_action`.
+    pub fn best_action(&self, rule: MoveSelection) -> Option<A> {
+        select_action(&self.root_stats(), rule)
+    }
+
+    /// See `MCTS::policy`.
+    pub fn policy(&self, temperature: f64) -> Vec<(A, f64)> {
+        visit_policy(&self.root_stats(), temperature)
+    }
+}
+
+/// Root-parallel MCTS: runs `workers` independent `MCTS` searches from `root_board` on their own
+/// threads, each with its own `Game`, evaluator clone and seed (`config.seed + worker index`),
+/// then merges their root statistics. `limits` applies to every worker separately. Panics under
+/// `RootSelection::Gumbel`, like `MCTS::search_with_limits`.
+pub fn root_parallel_search<G, F, E>(
+    make_game: F,
+    root_board: G::Board,
+    evaluator: E,
+    config: MCTSConfig,
+    workers: usize,
+    limits: &SearchLimits
+) -> RootParallelStats<G::Action>
+where
+    G: Game,
+    G::Action: Send,
+    G::Board: Send + Sync,
+    F: Fn() -> G + Sync,
+    E: Evaluator<G> + Clone + Send + Sync
+{
+    // fail here rather than in every worker
+    assert!(config.root_selection == RootSelection::PUCT, "Gumbel root selection needs a fixed budget, call search");
+    let worker_stats = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers.max(1))
+            .map(|worker_index| {
+                let (make_game, root_board, evaluator) = (&make_game, &root_board, &evaluator);
+                let config = MCTSConfig { seed: config.seed.wrapping_add(worker_index as u64), ..config.clone() };
+                scope.spawn(move || {
+                    let mut game = make_game();
+                    let root_state = game.get_state(root_board);
+                    let mut mcts = MCTS::with_evaluator(game, root_state, evaluator.clone(), config);
+                    mcts.search_with_limits(limits);
+                    mcts.root_stats()
+                })
+            })
+            .collect();
+        handles.into_iter().map(|handle| handle.join().expect("Root parallel worker panicked")).collect()
+    });
+    RootParallelStats::merge(worker_stats)
+}
@@ -0,0 +1,252 @@
+use std::rc::Rc;
+use rand::{Rng,SeedableRng};
+use rand::rngs::StdRng;
+use crate::evaluator::random_action;
+use crate::game::{Game,GameState};
+use crate::mcts::{ActionStats,MoveSelection,NodeId,select_action};
+
+/// Search parameters for `PuzzleMCTS`. Scores are normalized to [0, 1] by the lowest and highest
+/// score seen so far before any of these apply.
+#[derive(Debug, Clone)]
+pub struct PuzzleConfig {
+    pub c_uct: f64, // exploration constant in UCT
+    /// D in SP-MCTS: added to a child's score variance, scaled down by its visits, so children
+    /// with few visits or spread out scores keep being explored.
+    pub variance_bias: f64,
+    /// How much of a child's value is its best score rather than its mean, between 0 and 1.
+    /// Puzzles only need one good line, so the best score is often the better guide.
+    pub max_weight: f64,
+    pub seed: u64 // seeds the rollouts and the choice of untried actions
+}
+
+impl Default for PuzzleConfig {
+    fn default() -> Self {
+        PuzzleConfig {
+            c_uct: 0.5,
+            variance_bias: 1.,
+            max_weight: 0.5,
+            seed: 0
+        }
+    }
+}
+
+/// A node of the search tree, reached from its parent by `action`.
+#[derive(Debug, Clone)]
+pub struct PuzzleNode<S: GameState> {
+    pub game_state: Rc<S>,
+    pub action: Option<S::Action>, // None at the root
+    pub parent: Option<NodeId>,
+    pub children: Vec<NodeId>,
+    pub untried: Vec<S::Action>, // legal actions without a child yet
+    pub visits: u32,
+    pub total_score: f64,
+    pub total_squared_score: f64,
+    pub max_score: f64
+}
+
+impl<S: GameState> PuzzleNode<S> {
+    pub fn mean_score(&self) -> f64 {
+        if self.visits == 0 { 0. } else { self.total_score / self.visits as f64 }
+    }
+
+    pub fn variance(&self) -> f64 {
+        if self.visits == 0 { 0. } else { (self.total_squared_score / self.visits as f64 - self.mean_score().powi(2)).max(0.) }
+    }
+}
+
+/// Single-player MCTS (SP-MCTS) for puzzles and planning problems. The score is the root player's
+/// entry in `GameState::utilities` and can be any number, not just a win, draw or loss. Selection
+/// blends each child's normalized mean and best score, plus UCT and variance terms, and the search
+/// keeps the best complete action sequence any playout found. Searches a tree rather than a
+/// graph, since the best line found is what counts, and plays its own random rollouts rather
+/// than using an `Evaluator` so that their moves are known.
+pub struct PuzzleMCTS<G: Game> {
+    pub root: NodeId,
+    pub nodes: Vec<PuzzleNode<G::State>>,
+    pub game: G,
+    pub config: PuzzleConfig,
+    pub player: i32, // whose score is maximized, the player to move at the root
+    pub best_score: Option<f64>,
+    pub best_sequence: Vec<G::Action>, // from the root to a terminal state scoring best_score
+    min_seen: f64,
+    max_seen: f64,
+    rng: StdRng
+}
+
+impl<G: Game> PuzzleMCTS<G> {
+
+    pub fn new(game: G, root_state: Rc<G::State>) -> Self {
+        PuzzleMCTS::with_config(game, root_state, PuzzleConfig::default())
+    }
+
+    pub fn with_config(game: G, root_state: Rc<G::State>, config: PuzzleConfig) -> Self {
+        let player = *root_state.player();
+        let mut mcts = PuzzleMCTS {
+            root: NodeId(0),
+            nodes: Vec::new(),
+            game,
+            player,
+            best_score: None,
+            best_sequence: Vec::new(),
+            min_seen: f64::INFINITY,
+            max_seen: f64::NEG_INFINITY,
+            rng: StdRng::seed_from_u64(config.seed),
+            config
+        };
+        mcts.root = mcts.add_node(root_state, None, None);
+        mcts
+    }
+
+    pub fn node(&self, id: NodeId) -> &PuzzleNode<G::State> {
+        &self.nodes[id.0]
+    }
+
+    pub fn root_node(&self) -> &PuzzleNode<G::State> {
+        self.node(self.root)
+    }
+
+    fn add_node(&mut self, game_state: Rc<G::State>, action: Option<G::Action>, parent: Option<NodeId>) -> NodeId {
+        let untried = if *game_state.is_terminal() {
+            Vec::new()
+        } else {
+            game_state.all_legal_actions().clone().expect("No legal actions for a non-terminal state?")
+        };
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(PuzzleNode {
+            game_state,
+            action,
+            parent,
+            children: Vec::new(),
+            untried,
+            visits: 0,
+            total_score: 0.,
+            total_squared_score: 0.,
+            max_score: f64::NEG_INFINITY
+        });
+        id
+    }
+
+    /// One playout: select down the tree to a node with untried actions, add a child for one of
+    /// them, roll out from it and back the score up to the root.
+    pub fn run(&mut self) {
+        let mut id = self.root;
+        while self.nodes[id.0].untried.is_empty() && !self.nodes[id.0].children.is_empty() {
+            id = self.best_child(id);
+        }
+        if !self.nodes[id.0].untried.is_empty() {
+            let untried = &mut self.nodes[id.0].untried;
+            let action = untried.swap_remove(self.rng.gen_range(0..untried.len()));
+            let state = self.game.transition(self.nodes[id.0].game_state.clone(), action.clone());
+            let child = self.add_node(state, Some(action), Some(id));
+            self.nodes[id.0].children.push(child);
+            id = child;
+        }
+        let (score, rollout) = self.rollout(id);
+        self.record(id, score, rollout);
+        self.backprop(id, score);
+    }
+
+    pub fn search(&mut self, n: u32) {
+        for _ in 0..n { self.run() }
+    }
+
+    /// Random moves from `id` to the end of the game: the root player's score and the moves played.
+    fn rollout(&mut self, id: NodeId) -> (f64, Vec<G::Action>) {
+        let mut state = self.nodes[id.0].game_state.clone();
+        let mut actions = Vec::new();
+        while !*state.is_terminal() {
+            let action = random_action(&*state, &mut self.rng);
+            actions.push(action.clone());
+            state = self.game.transition(state, action);
+        }
+        (self.score(&*state), actions)
+    }
+
+    fn score(&self, state: &G::State) -> f64 {
+        let utilities = state.utilities().expect("No result for terminal state?");
+        utilities
+            .iter()
+            .find(|(player, _)| *player == self.player)
+            .or(utilities.first())
+            .expect("Empty result")
+            .1
+    }
+
+    /// Keeps the playout's sequence if it beats the best so far, and widens the score range.
+    fn record(&mut self, id: NodeId, score: f64, rollout: Vec<G::Action>) {
+        self.min_seen = self.min_seen.min(score);
+        self.max_seen = self.max_seen.max(score);
+        if self.best_score.is_some_and(|best| best >= score) {
+            return;
+        }
+        let mut sequence = Vec::new();
+        let mut cur = Some(id);
+        while let Some(node) = cur.map(|cur| &self.nodes[cur.0]) {
+            sequence.extend(node.action.clone());
+            cur = node.parent;
+        }
+        sequence.reverse();
+        sequence.extend(rollout);
+        self.best_score = Some(score);
+        self.best_sequence = sequence;
+    }
+
+    fn backprop(&mut self, id: NodeId, score: f64) {
+        let mut cur = Some(id);
+        while let Some(id) = cur {
+            let node = &mut self.nodes[id.0];
+            node.visits += 1;
+            node.total_score += score;
+            node.total_squared_score += score * score;
+            node.max_score = node.max_score.max(score);
+            cur = node.parent;
+        }
+    }
+
+    /// `score` mapped to [0, 1] over the scores seen so far, 0.5 while they're all the same.
+    pub fn normalize(&self, score: f64) -> f64 {
+        let range = self.max_seen - self.min_seen;
+        if range > 0. { (score - self.min_seen) / range } else { 0.5 }
+    }
+
+    /// The SP-MCTS selection value of `child` under a parent with `parent_visits`: the blend of
+    /// its normalized mean and best score, a UCT term, and its normalized variance plus D over
+    /// its visits.
+    pub fn selection_value(&self, child: &PuzzleNode<G::State>, parent_visits: u32) -> f64 {
+        let visits = child.visits.max(1) as f64;
+        let range = (self.max_seen - self.min_seen).max(f64::EPSILON);
+        let value = (1. - self.config.max_weight) * self.normalize(child.mean_score()) + self.config.max_weight * self.normalize(child.max_score);
+        let exploration = self.config.c_uct * f64::sqrt(f64::ln(parent_visits.max(1) as f64) / visits);
+        let deviation = f64::sqrt(child.variance() / (range * range) + self.config.variance_bias / visits);
+        value + exploration + deviation
+    }
+
+    fn best_child(&self, id: NodeId) -> NodeId {
+        let node = &self.nodes[id.0];
+        *node.children
+            .iter()
+            .max_by(|&&a, &&b| {
+                let value_a = self.selection_value(&self.nodes[a.0], node.visits);
+                let value_b = self.selection_value(&self.nodes[b.0], node.visits);
+                value_a.partial_cmp(&value_b).expect("Comparison failed due to NaN")
+            })
+            .expect("Called best_child on a leaf")
+    }
+
+    /// `(action, visits, mean score)` for every child of the root.
+    pub fn root_stats(&self) -> Vec<ActionStats<G::Action>> {
+        self.root_node()
+            .children
+            .iter()
+            .map(|&child| {
+                let node = &self.nodes[child.0];
+                (node.action.clone().expect("Child without an action"), node.visits, node.mean_score())
+            })
+            .collect()
+    }
+
+    /// See `MCTS::best_action`. The first move of `best_sequence` is usually what a puzzle wants.
+    pub fn best_action(&self, rule: MoveSelection) -> Option<G::Action> {
+        select_action(&self.root_stats(), rule)
+    }
+}
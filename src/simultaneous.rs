@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use rand::{Rng,SeedableRng};
+use rand::rngs::StdRng;
+use wyhash2::WyHash;
+use crate::evaluator::{Evaluation,Evaluator,RandomRollout};
+use crate::game::{GameState,SimultaneousGame};
+use crate::mcts::{ActionStats,MCTSConfig,MoveSelection,NodeId,select_action,visit_policy,zero_sum_reward};
+
+/// How each player picks their action at a simultaneous node.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SimultaneousSelection {
+    /// Decoupled UCT: every player picks by PUCT over their own action statistics, as if the
+    /// others weren't there. Root policies follow the visit counts.
+    DecoupledUCT,
+    /// Regret matching on sampled rewards, mixed with `exploration` of the uniform strategy.
+    /// Root policies are the average strategy, which converges to an equilibrium.
+    RegretMatching { exploration: f64 }
+}
+
+/// One player's statistics at a simultaneous node, indexed like `actions`.
+#[derive(Debug, Clone)]
+pub struct PlayerStats<A> {
+    pub player: i32,
+    pub actions: Vec<A>,
+    pub visits: Vec<u32>,
+    pub total_value: Vec<f64>, // sum of the playouts' rewards for `player`
+    pub regrets: Vec<f64>,
+    pub strategy_sum: Vec<f64> // regret matching strategies summed over the node's playouts
+}
+
+impl<A> PlayerStats<A> {
+    /// Mean reward for the player after choosing action `index`.
+    pub fn Q(&self, index: usize) -> f64 {
+        if self.visits[index] == 0 { 0. } else { self.total_value[index] / self.visits[index] as f64 }
+    }
+
+    /// Regret matching: positive regrets normalized, uniform if there are none, then mixed with
+    /// `exploration` of the uniform strategy.
+    pub fn strategy(&self, exploration: f64) -> Vec<f64> {
+        let uniform = 1. / self.actions.len() as f64;
+        let positive: f64 = self.regrets.iter().map(|regret| regret.max(0.)).sum();
+        self.regrets
+            .iter()
+            .map(|regret| {
+                let matched = if positive > 0. { regret.max(0.) / positive } else { uniform };
+                (1. - exploration) * matched + exploration * uniform
+            })
+            .collect()
+    }
+}
+
+/// A state in which every player in `players` chooses at once. `players` is empty until the node
+/// is expanded, and stays empty at terminal states.
+#[derive(Debug, Clone)]
+pub struct SimultaneousNode<S, A> {
+    pub game_state: Rc<S>,
+    pub N: u32, // visit count
+    pub players: Vec<PlayerStats<A>>,
+    pub children: HashMap<Vec<usize>, NodeId> // by the action index each player chose
+}
+
+/// MCTS for simultaneous-move games. Nodes are shared by transpositions like in `MCTS`, every
+/// player selects their own action from their own statistics by `SimultaneousSelection`, and the
+/// joint action picks the child. Values are plain means for each player rather than the MCGS
+/// regularized Q, and chance nodes aren't supported.
+pub struct SimultaneousMCTS<G: SimultaneousGame, E: Evaluator<G> = RandomRollout> {
+    pub root: NodeId,
+    pub nodes: Vec<SimultaneousNode<G::State, G::PlayerAction>>,
+    pub node_ids: HashMap<Rc<G::State>,NodeId,WyHash>,
+    pub game: G,
+    pub evaluator: E,
+    pub config: MCTSConfig, // uses c_puct and seed
+    pub selection: SimultaneousSelection,
+    rng: StdRng
+}
+
+impl<G: SimultaneousGame> SimultaneousMCTS<G, RandomRollout> {
+
+    pub fn new(game: G, root_state: Rc<G::State>, selection: SimultaneousSelection) -> Self {
+        SimultaneousMCTS::with_evaluator(game, root_state, RandomRollout, MCTSConfig::default(), selection)
+    }
+}
+
+impl<G: SimultaneousGame, E: Evaluator<G>> SimultaneousMCTS<G, E> {
+
+    pub fn with_evaluator(game: G, root_state: Rc<G::State>, evaluator: E, config: MCTSConfig, selection: SimultaneousSelection) -> Self {
+        let mut mcts = SimultaneousMCTS {
+            root: NodeId(0),
+            nodes: Vec::new(),
+            node_ids: HashMap::with_hasher(WyHash::with_seed(config.seed)),
+            game,
+            evaluator,
+            selection,
+            rng: StdRng::seed_from_u64(config.seed),
+            config
+        };
+        mcts.root = mcts.get_node(root_state);
+        mcts
+    }
+
+    pub fn node(&self, id: NodeId) -> &SimultaneousNode<G::State, G::PlayerAction> {
+        &self.nodes[id.0]
+    }
+
+    pub fn root_node(&self) -> &SimultaneousNode<G::State, G::PlayerAction> {
+        self.node(self.root)
+    }
+
+    /// The id of the node for `state`, adding an unexpanded one if needed.
+    pub fn get_node(&mut self, state: Rc<G::State>) -> NodeId {
+        if let Some(&id) = self.node_ids.get(&state) {
+            id
+        } else {
+            let id = NodeId(self.nodes.len());
+            self.nodes.push(SimultaneousNode { game_state: state.clone(), N: 0, players: Vec::new(), children: HashMap::new() });
+            self.node_ids.insert(state, id);
+            id
+        }
+    }
+
+    /// One playout: select joint actions down to a terminal or unexpanded node, expand and
+    /// evaluate it, and back the rewards up the path.
+    pub fn run(&mut self) {
+        let mut path: Vec<(NodeId, Vec<usize>)> = Vec::new();
+        let mut id = self.root;
+        let reward_map = loop {
+            let state = self.nodes[id.0].game_state.clone();
+            if *state.is_terminal() {
+                break Evaluation::<G::Action>::terminal(&*state).values;
+            }
+            if self.nodes[id.0].players.is_empty() {
+                self.expand(id);
+                break self.evaluator.evaluate(&mut self.game, state, &mut self.rng).values;
+            }
+            let choices: Vec<usize> = (0..self.nodes[id.0].players.len()).map(|seat| self.select(id, seat)).collect();
+            let child = match self.nodes[id.0].children.get(&choices) {
+                Some(&child) => child,
+                None => {
+                    let actions: Vec<G::PlayerAction> = self.nodes[id.0]
+                        .players
+                        .iter()
+                        .zip(&choices)
+                        .map(|(stats, &index)| stats.actions[index].clone())
+                        .collect();
+                    let joint_action = self.game.joint_action(&actions);
+                    let next_state = self.game.transition(state, joint_action);
+                    let child = self.get_node(next_state);
+                    self.nodes[id.0].children.insert(choices.clone(), child);
+                    child
+                }
+            };
+            path.push((id, choices));
+            id = child;
+        };
+        self.nodes[id.0].N += 1;
+        self.backprop(&path, &reward_map);
+    }
+
+    pub fn search(&mut self, n: u32) {
+        for _ in 0..n { self.run() }
+    }
+
+    fn expand(&mut self, id: NodeId) {
+        let node = &mut self.nodes[id.0];
+        node.players = self.game
+            .player_actions(&node.game_state)
+            .into_iter()
+            .map(|(player, actions)| {
+                let n = actions.len();
+                assert!(n > 0, "No actions for player {} at a non-terminal state?", player);
+                PlayerStats { player, actions, visits: vec![0; n], total_value: vec![0.; n], regrets: vec![0.; n], strategy_sum: vec![0.; n] }
+            })
+            .collect();
+    }
+
+    /// The action index for the player in `seat` at node `id`.
+    fn select(&mut self, id: NodeId, seat: usize) -> usize {
+        let node = &self.nodes[id.0];
+        let stats = &node.players[seat];
+        match self.selection {
+            SimultaneousSelection::DecoupledUCT => {
+                if let Some(unvisited) = stats.visits.iter().position(|&visits| visits == 0) {
+                    return unvisited;
+                }
+                (0..stats.actions.len())
+                    .map(|index| (index, self.PUCT(node, stats, index)))
+                    .max_by(|(_, puct_a), (_, puct_b)| puct_a.partial_cmp(puct_b).expect("Comparison failed due to NaN"))
+                    .expect("Called select on no actions")
+                    .0
+            }
+            SimultaneousSelection::RegretMatching { exploration } => {
+                let strategy = stats.strategy(exploration);
+                let mut sample: f64 = self.rng.gen();
+                for (index, probability) in strategy.iter().enumerate() {
+                    sample -= probability;
+                    if sample < 0. {
+                        return index;
+                    }
+                }
+                strategy.len() - 1
+            }
+        }
+    }
+
+    /// PUCT with a uniform prior over the player's own actions.
+    pub fn PUCT(&self, node: &SimultaneousNode<G::State, G::PlayerAction>, stats: &PlayerStats<G::PlayerAction>, index: usize) -> f64 {
+        stats.Q(index) + self.config.c_puct * f64::sqrt(node.N as f64) / (1 + stats.visits[index]) as f64
+    }
+
+    /// Updates every player's statistics for the action they chose. Under regret matching the
+    /// reward is importance weighted by the chance of having chosen the action, so each regret
+    /// update is an unbiased estimate of the full one.
+    fn backprop(&mut self, path: &[(NodeId, Vec<usize>)], reward_map: &HashMap<i32, f64>) {
+        for (id, choices) in path {
+            let node = &mut self.nodes[id.0];
+            node.N += 1;
+            for (stats, &chosen) in node.players.iter_mut().zip(choices) {
+                let reward = zero_sum_reward(reward_map, stats.player);
+                stats.visits[chosen] += 1;
+                stats.total_value[chosen] += reward;
+                if let SimultaneousSelection::RegretMatching { exploration } = self.selection {
+                    let strategy = stats.strategy(exploration);
+                    for (index, probability) in strategy.iter().enumerate() {
+                        let sampled = if index == chosen { reward / strategy[chosen] } else { 0. };
+                        stats.regrets[index] += sampled - reward;
+                        stats.strategy_sum[index] += probability;
+                    }
+                }
+            }
+        }
+    }
+
+    fn root_player_stats(&self, player: i32) -> Option<&PlayerStats<G::PlayerAction>> {
+        self.root_node().players.iter().find(|stats| stats.player == player)
+    }
+
+    /// `(action, visits, mean reward for player)` for each of `player`'s actions at the root,
+    /// empty if they aren't choosing there.
+    pub fn root_stats(&self, player: i32) -> Vec<ActionStats<G::PlayerAction>> {
+        self.root_player_stats(player)
+            .map(|stats| (0..stats.actions.len()).map(|index| (stats.actions[index].clone(), stats.visits[index], stats.Q(index))).collect())
+            .unwrap_or_default()
+    }
+
+    /// See `MCTS::best_action`, for `player`'s action at the root.
+    pub fn best_action(&self, player: i32, rule: MoveSelection) -> Option<G::PlayerAction> {
+        select_action(&self.root_stats(player), rule)
+    }
+
+    /// `player`'s strategy at the root: the average strategy under regret matching, the visit
+    /// distribution under decoupled UCT.
+    pub fn policy(&self, player: i32) -> Vec<(G::PlayerAction, f64)> {
+        match (self.selection, self.root_player_stats(player)) {
+            (SimultaneousSelection::RegretMatching { .. }, Some(stats)) => {
+                let total: f64 = stats.strategy_sum.iter().sum();
+                stats.actions
+                    .iter()
+                    .zip(&stats.strategy_sum)
+                    .map(|(action, sum)| (action.clone(), if total > 0. { sum / total } else { 1. / stats.actions.len() as f64 }))
+                    .collect()
+            }
+            _ => visit_policy(&self.root_stats(player), 1.)
+        }
+    }
+}
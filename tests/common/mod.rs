@@ -0,0 +1,86 @@
+#![allow(dead_code)] // each test crate uses its own part of this
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+use mcts_rs::game::{Game, GameState};
+
+/// A small game for a single test, defined by what each board looks like and how actions change it.
+pub struct Fixture<B, A> {
+    pub describe: fn(B) -> FixtureState<B, A>,
+    pub next: fn(&B, A) -> B
+}
+
+#[derive(Debug)]
+pub struct FixtureState<B, A> {
+    pub state: B,
+    pub player: i32,
+    pub result: Option<Vec<(i32, i32)>>,
+    pub is_terminal: bool,
+    pub all_legal_actions: Option<Vec<A>>,
+    pub utilities: Option<Vec<(i32, f64)>> // in place of `result` when set
+}
+
+impl<B, A> FixtureState<B, A> {
+    /// A state where `player` picks one of `actions`.
+    pub fn playing(state: B, player: i32, actions: Vec<A>) -> Self {
+        FixtureState { state, player, result: None, is_terminal: false, all_legal_actions: Some(actions), utilities: None }
+    }
+
+    /// A finished game, `player` being whoever would have moved next.
+    pub fn finished(state: B, player: i32, result: Vec<(i32, i32)>) -> Self {
+        FixtureState { state, player, result: Some(result), is_terminal: true, all_legal_actions: Some(vec![]), utilities: None }
+    }
+
+    /// Real-valued payoffs for a finished game, for margins and the like.
+    pub fn with_utilities(self, utilities: Vec<(i32, f64)>) -> Self {
+        FixtureState { utilities: Some(utilities), ..self }
+    }
+}
+
+// everything else follows from the board
+impl<B: PartialEq, A> PartialEq for FixtureState<B, A> {
+    fn eq(&self, other: &Self) -> bool { self.state == other.state }
+}
+
+impl<B: Eq, A> Eq for FixtureState<B, A> {}
+
+impl<B: Hash, A> Hash for FixtureState<B, A> {
+    fn hash<H: Hasher>(&self, hasher: &mut H) { self.state.hash(hasher) }
+}
+
+impl<B, A> GameState for FixtureState<B, A>
+where
+    B: Clone + Eq + Hash + Debug,
+    A: Clone + Eq + Hash + Debug
+{
+    type Action = A;
+    type Board = B;
+    fn state(&self) -> &B { &self.state }
+    fn is_terminal(&self) -> &bool { &self.is_terminal }
+    fn player(&self) -> &i32 { &self.player }
+    fn result(&self) -> &Option<Vec<(i32, i32)>> { &self.result }
+    fn all_legal_actions(&self) -> &Option<Vec<A>> { &self.all_legal_actions }
+    fn utilities(&self) -> Option<Vec<(i32, f64)>> {
+        match &self.utilities {
+            Some(utilities) => Some(utilities.clone()),
+            None => self.result.as_ref().map(|result| result.iter().map(|&(player, reward)| (player, reward as f64)).collect())
+        }
+    }
+}
+
+impl<B, A> Game for Fixture<B, A>
+where
+    B: Clone + Eq + Hash + Debug,
+    A: Clone + Eq + Hash + Debug
+{
+    type Action = A;
+    type Board = B;
+    type State = FixtureState<B, A>;
+    fn get_state(&mut self, board: &B) -> Rc<FixtureState<B, A>> {
+        Rc::new((self.describe)(board.clone()))
+    }
+    fn transition(&mut self, game_state: Rc<FixtureState<B, A>>, action: A) -> Rc<FixtureState<B, A>> {
+        let board = (self.next)(&game_state.state, action);
+        self.get_state(&board)
+    }
+}
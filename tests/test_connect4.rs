@@ -1,7 +1,7 @@
 use std::rc::Rc;
 use ndarray::prelude::*;
 use mcts_rs::games::connect4::{Connect4State, Connect4};
-use mcts_rs::game::Game;
+use mcts_rs::game::{Game, ToArray2};
 
 #[test] 
 fn test_connect4_all_legal_actions() {
@@ -15,7 +15,7 @@ fn test_connect4_all_legal_actions() {
     ]);
     let mut connect4 = Connect4::new();
     let initial_state = connect4.get_state(&cant_lose);
-    let expected_actions = vec![(0, 0), (1, 2), (0, 6)];
+    let expected_actions = vec![0, 2, 6];
 
     assert_eq!(initial_state.all_legal_actions.clone().unwrap(), expected_actions,
         "The legal actions do not match the expected actions");
@@ -48,4 +48,23 @@ fn test_connect4_finds_all_states() {
 
     // Assert that the number of states is exactly 5478
     assert_eq!(connect4.game_states.len(), 16, "The number of states should be 5478");
-}
\ No newline at end of file
+}
+#[test]
+fn test_connect4_array2_view() {
+    let mut connect4 = Connect4::new();
+    let empty = connect4.get_state(&Array2::zeros((6, 7)));
+    let state = connect4.transition(empty, 3);
+    let state = connect4.transition(state, 3);
+
+    let grid = state.to_array2();
+    assert_eq!(grid.dim(), (6, 7));
+    assert_eq!((grid[[5, 3]], grid[[4, 3]]), (1, -1), "Pieces stack up from the bottom row");
+    assert_eq!(grid.iter().filter(|&&cell| cell != 0).count(), 2);
+    assert_eq!(state.to_string(), grid.to_string(), "States display as their grid");
+
+    // player 1 is to move again, so their piece is in the first plane
+    let planes = state.to_planes(state.player);
+    assert_eq!(planes.dim(), (2, 6, 7));
+    assert_eq!((planes[[0, 5, 3]], planes[[1, 4, 3]]), (1., 1.));
+    assert_eq!(planes.sum(), 2.);
+}
@@ -0,0 +1,37 @@
+use mcts_rs::games::dots_and_boxes::DotsAndBoxes;
+use mcts_rs::game::Game;
+
+#[test]
+fn test_dots_and_boxes_all_legal_actions() {
+    let mut dots_and_boxes = DotsAndBoxes::new(2, 2);
+    assert_eq!(dots_and_boxes.lines(), 12);
+
+    let initial_state = dots_and_boxes.get_state(&(0b1010_0000_0101, 1, 0));
+    assert_eq!(initial_state.all_legal_actions.clone().unwrap(), vec![1, 3, 4, 5, 6, 7, 8, 10],
+        "The legal actions do not match the undrawn lines");
+    assert!(!initial_state.is_terminal);
+}
+
+#[test]
+fn test_dots_and_boxes_completing_a_box_moves_again() {
+    let mut dots_and_boxes = DotsAndBoxes::new(2, 2);
+    // the top left box is lines 0, 2, 6 and 7, all drawn but 7
+    let state = dots_and_boxes.get_state(&(0b0000_0100_0101, 1, 0));
+
+    let captured = dots_and_boxes.transition(state.clone(), 7);
+    assert_eq!(captured.state, (0b0000_1100_0101, 1, 1), "Player 1 takes the box and moves again");
+
+    let passed = dots_and_boxes.transition(state, 8);
+    assert_eq!(passed.player, -1, "No box, so the turn passes");
+    assert_eq!(passed.state.2, 0);
+}
+
+#[test]
+fn test_dots_and_boxes_result() {
+    let mut dots_and_boxes = DotsAndBoxes::new(1, 2);
+    let state = dots_and_boxes.get_state(&(0b011_1111, -1, -2));
+
+    let finished = dots_and_boxes.transition(state, 6);
+    assert!(finished.is_terminal);
+    assert_eq!(finished.result, Some(vec![(1, -1), (-1, 1)]), "Player -1 has more boxes");
+}
@@ -0,0 +1,29 @@
+use mcts_rs::games::goofspiel::{Goofspiel, GoofspielBoard};
+use mcts_rs::game::{Game, SimultaneousGame};
+
+#[test]
+fn test_goofspiel_all_legal_actions() {
+    let mut goofspiel = Goofspiel;
+    let initial_state = goofspiel.get_state(&GoofspielBoard::new(2));
+
+    assert_eq!(initial_state.all_legal_actions.clone().unwrap(), vec![(1, 1), (1, 2), (2, 1), (2, 2)],
+        "Every pair of bids is a joint action");
+    assert_eq!(goofspiel.player_actions(&initial_state), vec![(1, vec![1, 2]), (-1, vec![1, 2])]);
+    assert_eq!(goofspiel.joint_action(&[2, 1]), (2, 1));
+}
+
+#[test]
+fn test_goofspiel_higher_bid_takes_the_prize() {
+    let mut goofspiel = Goofspiel;
+    let initial_state = goofspiel.get_state(&GoofspielBoard::new(3));
+
+    let round = goofspiel.transition(initial_state, (1, 3));
+    assert_eq!(round.state.score, -3, "Player -1 takes the 3");
+    assert_eq!(round.state.hands, [vec![2, 3], vec![1, 2]]);
+    let tie = goofspiel.transition(round, (2, 2));
+    assert_eq!(tie.state.score, -3, "A tie takes nothing");
+    let finished = goofspiel.transition(tie, (3, 1));
+    assert!(finished.is_terminal);
+    assert_eq!(finished.result, Some(vec![(1, -1), (-1, 1)]), "3 to 1 for player -1");
+    assert!(goofspiel.player_actions(&finished).is_empty());
+}
@@ -0,0 +1,47 @@
+use mcts_rs::game::Game;
+use mcts_rs::games::kuhn::{Kuhn, KuhnAction, KuhnBoard};
+use mcts_rs::ismcts::ISMCTS;
+use mcts_rs::mcts::MoveSelection;
+use KuhnAction::{Bet, Pass};
+
+fn search_kuhn(cards: [u8; 2], history: Vec<KuhnAction>) -> ISMCTS<Kuhn> {
+    let mut game = Kuhn;
+    let start = game.get_state(&KuhnBoard { cards, history });
+    let mut ismcts = ISMCTS::new(game, start);
+    ismcts.search(500);
+    ismcts
+}
+
+#[test]
+fn test_calls_with_the_king_and_folds_the_jack() {
+    // the opponent's card is listed as a queen, but the search must not peek at it
+    let king = search_kuhn([2, 1], vec![Pass, Bet]);
+    assert_eq!(king.best_action(MoveSelection::MaxVisits), Some(Bet), "The king always wins a showdown");
+    let jack = search_kuhn([0, 1], vec![Pass, Bet]);
+    assert_eq!(jack.best_action(MoveSelection::MaxVisits), Some(Pass), "The jack always loses a showdown");
+
+    let call = jack.root_stats().into_iter().find(|(action, _, _)| *action == Bet).unwrap();
+    assert!(call.1 > 0, "Calling was tried");
+    assert_eq!(call.2, -2., "Calling with the jack loses 2 in every determinization");
+}
+
+#[test]
+fn test_queen_call_averages_over_hidden_cards() {
+    // the queen beats a jack and loses to a king, so calling is worth 0 on average against a fold's -1,
+    // even though the opponent really holds the king
+    let queen = search_kuhn([1, 2], vec![Pass, Bet]);
+    assert_eq!(queen.best_action(MoveSelection::MaxVisits), Some(Bet));
+    let call = queen.root_stats().into_iter().find(|(action, _, _)| *action == Bet).unwrap();
+    assert!(call.2.abs() < 0.5, "Calling should average out to about 0, got {}", call.2);
+}
+
+#[test]
+fn test_nodes_are_shared_per_information_set() {
+    let ismcts = search_kuhn([2, 0], vec![]);
+
+    assert!(ismcts.node_ids.contains_key(&(-1, (0, vec![Bet]))), "Player -1 holding the jack was sampled");
+    assert!(ismcts.node_ids.contains_key(&(-1, (1, vec![Bet]))), "Player -1 holding the queen was sampled");
+    assert!(!ismcts.node_ids.contains_key(&(-1, (2, vec![Bet]))), "Player 1 holds the king, so player -1 can't");
+    assert_eq!(ismcts.nodes.len(), ismcts.node_ids.len(), "One node per information set");
+    assert_eq!(ismcts.root_node().N, 500, "Every playout goes through the root");
+}
@@ -0,0 +1,30 @@
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use mcts_rs::games::kuhn::{Kuhn, KuhnAction, KuhnBoard};
+use mcts_rs::game::{Game, InformationSetGame};
+use KuhnAction::{Bet, Pass};
+
+#[test]
+fn test_kuhn_results() {
+    let mut kuhn = Kuhn;
+    let result = |kuhn: &mut Kuhn, cards, history| kuhn.get_state(&KuhnBoard { cards, history }).result.clone();
+
+    assert_eq!(result(&mut kuhn, [2, 0], vec![Pass, Pass]), Some(vec![(1, 1), (-1, -1)]), "The king takes the antes");
+    assert_eq!(result(&mut kuhn, [0, 1], vec![Pass, Bet, Bet]), Some(vec![(1, -2), (-1, 2)]), "A called bet doubles the pot");
+    assert_eq!(result(&mut kuhn, [0, 2], vec![Bet, Pass]), Some(vec![(1, 1), (-1, -1)]), "Folding loses whatever the cards");
+    assert_eq!(result(&mut kuhn, [0, 2], vec![Pass, Bet]), None, "Player 1 still has to call or fold");
+}
+
+#[test]
+fn test_kuhn_determinize_keeps_own_card() {
+    let mut kuhn = Kuhn;
+    let mut rng = StdRng::seed_from_u64(0);
+    let state = kuhn.get_state(&KuhnBoard { cards: [1, 2], history: vec![Pass] });
+
+    for _ in 0..20 {
+        let determinized = kuhn.determinize(&state, -1, &mut rng);
+        assert_eq!(determinized.state.cards[1], 2, "Player -1 knows their own card");
+        assert_ne!(determinized.state.cards[0], 2, "Player 1 can't hold the same card");
+        assert_eq!(kuhn.information_set(&determinized, -1), kuhn.information_set(&state, -1));
+    }
+}
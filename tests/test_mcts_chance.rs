@@ -0,0 +1,48 @@
+use mcts_rs::game::Game;
+use mcts_rs::games::pig::{Pig, PigAction, PigBoard};
+use mcts_rs::mcts::{CyclePolicy, MCTS, MCTSConfig, MoveSelection};
+
+// Pig to 10
+const TARGET: i32 = 10;
+
+fn search_pig(scores: [i32; 2], turn_total: i32) -> MCTS<Pig> {
+    let mut game = Pig::new(TARGET);
+    let start = game.get_state(&PigBoard { scores, turn_total, player: 1, rolling: false });
+    // rolling 1s back and forth can revisit a state, keep those apart rather than calling them draws
+    let config = MCTSConfig { cycles: CyclePolicy::PathDependent, ..Default::default() };
+    let mut mcts = MCTS::with_config(game, start, config);
+    mcts.search(2000);
+    mcts
+}
+
+#[test]
+fn test_pig_holds_when_holding_wins() {
+    let mcts = search_pig([8, 0], 2);
+
+    assert_eq!(mcts.best_action(MoveSelection::MaxVisits), Some(PigAction::Hold), "Holding banks the win");
+}
+
+#[test]
+fn test_pig_rolls_when_opponent_is_about_to_win() {
+    // holding only gets to 9, and the opponent at 9 wins unless they roll a 1
+    let mcts = search_pig([7, 9], 2);
+
+    assert_eq!(mcts.best_action(MoveSelection::MaxVisits), Some(PigAction::Roll), "Rolling wins 5 times in 6");
+    let stats = mcts.root_stats();
+    let roll = stats.iter().find(|(action, _, _)| *action == PigAction::Roll).unwrap().2;
+    assert!(roll > 0.5, "Rolling should be worth most of a win, got {}", roll);
+}
+
+#[test]
+fn test_chance_node_backs_up_expectation() {
+    let mcts = search_pig([7, 9], 2);
+
+    let root = mcts.root_node();
+    let rolled = root.edges.iter().find(|edge| edge.action == PigAction::Roll).unwrap().child;
+    let chance = mcts.node(rolled);
+    assert_eq!(chance.edges.len(), 6, "One edge per face");
+    assert!(chance.edges.iter().all(|edge| (edge.prior - 1. / 6.).abs() < 1e-12), "Edges carry the outcome probabilities");
+
+    let expectation: f64 = chance.edges.iter().map(|edge| edge.prior * mcts.child_Q(chance, edge.child)).sum();
+    assert!((chance.Q + expectation).abs() < 1e-12, "Q should be the expected outcome, {} vs {}", chance.Q, -expectation);
+}
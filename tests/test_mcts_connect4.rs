@@ -1,7 +1,8 @@
 use ndarray::prelude::*;
 use mcts_rs::game::Game;
-use mcts_rs::games::connect4::Connect4;
-use mcts_rs::mcts::MCTS;
+use mcts_rs::evaluator::{HeuristicEvaluator, TruncatedRollout};
+use mcts_rs::games::connect4::{Connect4, Connect4State};
+use mcts_rs::mcts::{Backup, MCTS, MCTSConfig, MoveSelection, Proof, SearchLimits};
 
 #[test]
 fn test_mcts_chooses_winning_move() {
@@ -18,38 +19,8 @@ fn test_mcts_chooses_winning_move() {
     let mut mcts = MCTS::new(connect4,one_move_to_win);
     mcts.search(50);
 
-    let child_states = {
-        let root = mcts.root.borrow();
-        root.child_to_edge_visits
-            .keys()
-            .cloned() 
-            .collect::<Vec<_>>()
-    };
-
-    let child_nodes = child_states
-        .into_iter()
-        .map(|child_state_rc| mcts.get_node(child_state_rc))
-        .collect::<Vec<_>>();
-
-    let winning_node = child_nodes
-        .into_iter()
-        .max_by(|a, b| {
-            let a_q = a.borrow().Q;
-            let b_q = b.borrow().Q;
-            a_q.partial_cmp(&b_q).unwrap()
-        }).expect("No child found");
-
-    let winning_child_state = &winning_node.borrow().game_state.state;
-    let answer = arr2(&[
-        [0, 0, 0, 0, 0, 0, 0],
-        [0, 0, 0, 0, 0, 0, 0],
-        [0, 0, 0, 1, 0, 0, 0],
-        [0, 0, 0, 1, 0, 0, 0],
-        [0, 0, -1, 1, 0, 0, 0],
-        [0, 0, -1, 1, -1, 0, 0],
-    ]);
-    
-    assert_eq!(*winning_child_state, answer, "MCTS did not pick the winning move");
+    let winning_action = mcts.best_action(MoveSelection::MaxQ).expect("No child found");
+    assert_eq!(winning_action, 3, "MCTS did not pick the winning move");
 }
 
 #[test]
@@ -68,9 +39,9 @@ fn test_mcts_results_contain_no_losses() {
     let mut mcts = MCTS::new(connect4,win_or_draw);
     mcts.search(50);
 
-    let root = mcts.root.borrow();
-    let losses_for_o = *root.results.get(&-1).expect("results broken");
-    assert_eq!(losses_for_o, 0 ,"Expected zero losses for player -1 at the root");
+    let root = mcts.root_node();
+    assert!(root.stats.count > 0, "Rewards were backed up through the root");
+    assert!(root.stats.min >= 0., "Expected zero losses for the player to move at the root");
 }
 
 #[test]
@@ -85,41 +56,11 @@ fn test_mcts_blocks_win() {
         [0, 0, -1, 1, -1, 0, 0]
     ]);
     let o_can_win = connect4.get_state(&board);
-    let blocked = arr2(&[
-        [0, 0, 0, 0, 0, 0, 0],
-        [0, 0, 0, 1, 0, 0, 0],
-        [0, 0, 1, -1, 0, 0, 0],
-        [0, 0, -1, 1, 0, 0, 0],
-        [0, 0, -1, 1, 1, 0, 0],
-        [0, 0, -1, 1, -1, 0, 0]
-    ]);
     let mut mcts = MCTS::new(connect4,o_can_win);
     mcts.search(50);
 
-    let child_states = {
-        let root = mcts.root.borrow();
-        root.child_to_edge_visits
-            .keys()
-            .cloned()
-            .collect::<Vec<_>>()
-    };
-
-    let child_nodes = child_states
-        .into_iter()
-        .map(|child_state_rc| mcts.get_node(child_state_rc.clone()))
-        .collect::<Vec<_>>();
-
-    let chosen_node = child_nodes
-        .into_iter()
-        .max_by(|a, b| {
-            let a_q = a.borrow().Q;
-            let b_q = b.borrow().Q;
-            a_q.partial_cmp(&b_q).unwrap()
-        })
-        .expect("No child found");
-
-    let chosen_state = &chosen_node.borrow().game_state.state;
-    assert_eq!(*chosen_state, blocked, "MCTS did not block the winning move");
+    let chosen_action = mcts.best_action(MoveSelection::MaxQ).expect("No child found");
+    assert_eq!(chosen_action, 2, "MCTS did not block the winning move");
 }
 
 #[test]
@@ -129,7 +70,101 @@ fn test_one_run_expands_and_selects_one() {
     let new_game = connect4.get_state(&empty_board);
     let mut mcts = MCTS::new(connect4, new_game);
     mcts.run();
-    assert_eq!(mcts.root.borrow().N, 8, "One run visits the root and all it's children. 1 + 7 = 8 = root.N");
+    assert_eq!(mcts.root_node().N, 8, "One run visits the root and all it's children. 1 + 7 = 8 = root.N");
     mcts.run();
-    assert_eq!(mcts.root.borrow().N, 9, "one more run has only one path up to root so + 1 more");
-}
\ No newline at end of file
+    assert_eq!(mcts.root_node().N, 9, "one more run has only one path up to root so + 1 more");
+}
+
+#[test]
+fn test_truncated_rollout_finds_winning_move() {
+    let mut connect4 = Connect4::new();
+    let board = arr2(&[
+        [0, 0, 0, 0, 0, 0, 0],
+        [0, 0, 0, 0, 0, 0, 0],
+        [0, 0, 0, 0, 0, 0, 0],
+        [0, 0, 0, 1, 0, 0, 0],
+        [0, 0, -1, 1, 0, 0, 0],
+        [0, 0, -1, 1, -1, 0, 0],
+    ]);
+    let one_move_to_win = connect4.get_state(&board);
+    // no random moves at all: only terminal states carry any signal
+    let evaluator = TruncatedRollout {
+        max_depth: 0,
+        cutoff: HeuristicEvaluator(|_: &Connect4State| [(1, 0.), (-1, 0.)].into_iter().collect())
+    };
+    let mut mcts = MCTS::with_evaluator(connect4, one_move_to_win, evaluator, MCTSConfig::default());
+    mcts.search(50);
+
+    let winning_action = mcts.best_action(MoveSelection::MaxQ).expect("No child found");
+    assert_eq!(winning_action, 3, "MCTS did not pick the winning move");
+}
+
+#[test]
+fn test_incremental_backup_matches_idempotent_decisions() {
+    let board = arr2(&[
+        [0, 0, 0, 0, 0, 0, 0],
+        [0, 0, 0, 1, 0, 0, 0],
+        [0, 0, 0, -1, 0, 0, 0],
+        [0, 0, -1, 1, 0, 0, 0],
+        [0, 0, -1, 1, 1, 0, 0],
+        [0, 0, -1, 1, -1, 0, 0]
+    ]);
+    for backup in [Backup::Idempotent, Backup::Incremental] {
+        let mut connect4 = Connect4::new();
+        let o_can_win = connect4.get_state(&board);
+        let config = MCTSConfig { backup, ..Default::default() };
+        let mut mcts = MCTS::with_config(connect4, o_can_win, config);
+        mcts.search(60);
+
+        let root = mcts.root_node();
+        let edge_visits: u32 = root.edges.iter().map(|edge| edge.visits).sum();
+        assert_eq!(root.N, 1 + edge_visits, "{:?} backup should keep N = 1 + sum of edge visits at the root", backup);
+        let chosen_action = mcts.best_action(MoveSelection::MaxVisits).expect("No child found");
+        assert_eq!(chosen_action, 2, "MCTS with {:?} backup did not block the winning move", backup);
+    }
+}
+
+#[test]
+fn test_solver_proves_winning_move() {
+    let mut connect4 = Connect4::new();
+    let board = arr2(&[
+        [0, 0, 0, 0, 0, 0, 0],
+        [0, 0, 0, 0, 0, 0, 0],
+        [0, 0, 0, 0, 0, 0, 0],
+        [0, 0, 0, 1, 0, 0, 0],
+        [0, 0, -1, 1, 0, 0, 0],
+        [0, 0, -1, 1, -1, 0, 0],
+    ]);
+    let one_move_to_win = connect4.get_state(&board);
+    let config = MCTSConfig { solver: true, ..Default::default() };
+    let mut mcts = MCTS::with_config(connect4, one_move_to_win, config);
+    mcts.search(10);
+
+    let winning_edge = mcts.root_node().edges.iter().find(|edge| edge.action == 3).unwrap();
+    assert_eq!(mcts.node(winning_edge.child).proven, Some(Proof::Win), "Playing the fourth in a row is a proven win");
+    assert_eq!(mcts.root_node().proven, Some(Proof::Loss), "The root is lost for the player who moved into it");
+    assert_eq!(mcts.root_node().Q, -1., "Proven nodes report their exact value");
+    assert_eq!(mcts.best_action(MoveSelection::MaxVisits), Some(3), "MCTS did not pick the proven win");
+}
+
+#[test]
+fn test_solver_proves_endgame_and_stops_early() {
+    let mut connect4 = Connect4::new();
+    let endgame = arr2(&[
+        [0, -1, 0, -1, 1, -1, 0],
+        [-1, 1, 0, 1, -1, 1, -1],
+        [-1, 1, 1, 1, -1, -1, 1],
+        [1, -1, 1, -1, 1, -1, 1],
+        [1, -1, 1, -1, 1, -1, 1],
+        [-1, 1, -1, 1, -1, 1, -1],
+    ]);
+    let forced_win = connect4.get_state(&endgame);
+    let config = MCTSConfig { solver: true, ..Default::default() };
+    let mut mcts = MCTS::with_config(connect4, forced_win, config);
+    let limits = SearchLimits { playouts: Some(10_000), early_stop: true, ..Default::default() };
+    let playouts = mcts.search_with_limits(&limits);
+
+    assert!(mcts.root_node().proven.is_some(), "The whole endgame should be solved");
+    assert!(playouts < 10_000, "Search should stop once the root is proven");
+    assert_eq!(mcts.root_node().proven, Some(Proof::Loss), "Player 1 to move can force a win, so the root is lost for -1");
+}
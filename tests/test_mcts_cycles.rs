@@ -0,0 +1,101 @@
+mod common;
+
+use mcts_rs::game::Game;
+use mcts_rs::mcts::{Backup, CyclePolicy, MCTS, MCTSConfig, MoveSelection};
+use common::{Fixture, FixtureState};
+
+// A token on cells 0..=4, starting in the middle. Each turn the player to move shifts it one cell
+// left (-1) or right (1). Player 1 wins on reaching 4 and player -1 on reaching 0, so with best play the
+// token shuttles between 2 and 3 forever. The board is (cell, player to move).
+type Shuttle = Fixture<(i8, i32), i8>;
+
+const SHUTTLE: Shuttle = Fixture {
+    describe: |state| match state.0 {
+        4 => FixtureState::finished(state, state.1, vec![(1, 1), (-1, -1)]),
+        0 => FixtureState::finished(state, state.1, vec![(1, -1), (-1, 1)]),
+        _ => FixtureState::playing(state, state.1, vec![-1, 1])
+    },
+    next: |&(cell, player), action| (cell + action, -player)
+};
+
+fn search_shuttle(cycles: CyclePolicy) -> MCTS<Shuttle> {
+    let mut game = SHUTTLE;
+    let start = game.get_state(&(2, 1));
+    let config = MCTSConfig { cycles, ..Default::default() };
+    let mut mcts = MCTS::with_config(game, start, config);
+    mcts.search(500);
+    mcts
+}
+
+fn right_child_q(mcts: &MCTS<Shuttle>) -> f64 {
+    let right = mcts.root_node().edges.iter().find(|edge| edge.action == 1).unwrap();
+    mcts.node(right.child).Q
+}
+
+#[test]
+fn test_repetition_scored_as_draw() {
+    let mcts = search_shuttle(CyclePolicy::Draw);
+
+    assert_eq!(mcts.best_action(MoveSelection::MaxVisits), Some(1), "Moving left loses on the spot");
+    assert!(right_child_q(&mcts).abs() < 0.3, "Shuttling forever should be worth about a draw, got {}", right_child_q(&mcts));
+}
+
+#[test]
+fn test_repetition_penalized() {
+    let mcts = search_shuttle(CyclePolicy::Penalty(1.));
+
+    assert_eq!(mcts.best_action(MoveSelection::MaxVisits), Some(1));
+    assert!(right_child_q(&mcts) > 0.5, "Player -1 has to repeat or lose, got {}", right_child_q(&mcts));
+}
+
+#[test]
+fn test_path_dependent_unrolls_cycles() {
+    let mcts = search_shuttle(CyclePolicy::PathDependent);
+
+    assert_eq!(mcts.best_action(MoveSelection::MaxVisits), Some(1));
+    assert!(mcts.nodes.len() > mcts.node_ids.len(), "Repeats should get nodes of their own");
+    let root_state = mcts.root_node().game_state.clone();
+    assert_eq!(mcts.node_ids[&root_state], mcts.root, "The shared node for the start is still the root");
+}
+
+#[test]
+fn test_path_dependent_reuses_copies() {
+    let mut mcts = search_shuttle(CyclePolicy::PathDependent);
+    let nodes = mcts.nodes.len();
+    mcts.search(2000);
+
+    assert_eq!(mcts.nodes.len(), nodes, "Going round the cycle again should reuse the copies, not make more");
+    assert_eq!(mcts.best_action(MoveSelection::MaxVisits), Some(1));
+}
+
+// Player 1 at A can win on the spot or go to B, where player -1 can only resign or go back to A.
+// Going back repeats A, so repeating is the only way player -1 saves the game.
+const A: u8 = 0;
+const B: u8 = 1;
+const WON: u8 = 2;
+
+const STANDOFF: Fixture<u8, u8> = Fixture {
+    describe: |state| match state {
+        A => FixtureState::playing(state, 1, vec![WON, B]),
+        B => FixtureState::playing(state, -1, vec![A, WON]),
+        _ => FixtureState::finished(state, -1, vec![(1, 1), (-1, -1)])
+    },
+    next: |_, action| action
+};
+
+#[test]
+fn test_forced_repetition_saves_the_game() {
+    let policies = [(CyclePolicy::Draw, 0.), (CyclePolicy::Penalty(1.), 1.)];
+    for ((cycles, expected), backup) in policies.into_iter().flat_map(|policy| [Backup::Idempotent, Backup::Incremental].map(|backup| (policy, backup))) {
+        let mut game = STANDOFF;
+        let start = game.get_state(&A);
+        let mut mcts = MCTS::with_config(game, start, MCTSConfig { cycles, backup, ..Default::default() });
+        mcts.search(500);
+
+        let b = mcts.root_node().edges[1].child;
+        let q = mcts.node(b).Q;
+        assert!((q - expected).abs() < 0.3, "{:?}, {:?} backup: B should be worth {} to player 1, got {}", cycles, backup, expected, q);
+        let back = &mcts.node(b).edges[0];
+        assert_eq!(back.repeats, back.visits, "Going back from B always repeats A");
+    }
+}
@@ -0,0 +1,39 @@
+use mcts_rs::game::{Game, GameState};
+use mcts_rs::games::dots_and_boxes::DotsAndBoxes;
+use mcts_rs::mcts::{Backup, MCTS, MCTSConfig, MoveSelection, Proof};
+
+// Two boxes side by side, with every line but the middle one (5) and the right one (6) drawn. Drawing 5 takes the left box
+// and, on the extra turn, 6 takes the right one. Drawing 6 hands both boxes to player -1.
+fn two_box_endgame(config: MCTSConfig) -> MCTS<DotsAndBoxes> {
+    let mut game = DotsAndBoxes::new(1, 2);
+    let start = game.get_state(&(0b001_1111, 1, 0));
+    let mut mcts = MCTS::with_config(game, start, config);
+    mcts.search(200);
+    mcts
+}
+
+#[test]
+fn test_extra_turn_keeps_value_sign() {
+    for backup in [Backup::Idempotent, Backup::Incremental] {
+        let mcts = two_box_endgame(MCTSConfig { backup, ..Default::default() });
+
+        assert_eq!(mcts.best_action(MoveSelection::MaxVisits), Some(5), "{:?} backup missed the double capture", backup);
+        let stats = mcts.root_stats();
+        let take = stats.iter().find(|(action, _, _)| *action == 5).unwrap().2;
+        let give = stats.iter().find(|(action, _, _)| *action == 6).unwrap().2;
+        assert!(take > 0.9, "Capturing both boxes is a sure win for player 1, got {}", take);
+        assert!(give < -0.9, "Giving both boxes away is a sure loss for player 1, got {}", give);
+
+        let extra_turn = mcts.root_node().edges.iter().find(|edge| edge.action == 5).unwrap().child;
+        assert_eq!(*mcts.node(extra_turn).game_state.player(), 1, "Player 1 moves again after a capture");
+        assert!(mcts.node(extra_turn).Q < -0.9, "Q is for the opponent of the player to move, got {}", mcts.node(extra_turn).Q);
+    }
+}
+
+#[test]
+fn test_solver_follows_extra_turns() {
+    let mcts = two_box_endgame(MCTSConfig { solver: true, ..Default::default() });
+
+    assert_eq!(mcts.root_node().proven, Some(Proof::Loss), "Player 1 to move has a forced win");
+    assert_eq!(mcts.best_action(MoveSelection::MaxQ), Some(5));
+}
@@ -0,0 +1,74 @@
+use ndarray::prelude::*;
+use mcts_rs::game::Game;
+use mcts_rs::games::tictactoe::TicTacToe;
+use mcts_rs::mcts::{MCTS, MCTSConfig, RootSelection, SearchLimits};
+
+const GUMBEL: RootSelection = RootSelection::Gumbel { considered_actions: 16, c_visit: 50., c_scale: 1. };
+
+fn search_almost_won(root_selection: RootSelection, n: u32) -> MCTS<TicTacToe> {
+    let mut tictactoe = TicTacToe::new();
+    let one_move_to_win = arr2(&[
+        [ 1, -1,  0],
+        [ 1,  1, -1],
+        [-1,  0,  0]]);
+    let almost_won = tictactoe.get_state(&one_move_to_win);
+    let mut mcts = MCTS::with_config(tictactoe, almost_won, MCTSConfig { root_selection, ..Default::default() });
+    mcts.search(n);
+    mcts
+}
+
+#[test]
+fn test_gumbel_picks_winning_move_with_few_playouts() {
+    for playouts in [4, 10, 50] {
+        let mcts = search_almost_won(GUMBEL, playouts);
+
+        assert_eq!(mcts.gumbel_action(), Some((2, 2)), "Sequential halving missed the win with {} playouts", playouts);
+    }
+}
+
+#[test]
+fn test_completed_q_policy_improves_on_prior() {
+    let mcts = search_almost_won(GUMBEL, 10);
+
+    let policy = mcts.completed_q_policy(50., 1.);
+    let total: f64 = policy.iter().map(|(_, probability)| probability).sum();
+    assert!((total - 1.).abs() < 1e-9, "Policy sums to {}", total);
+    let win = policy.iter().find(|(action, _)| *action == (2, 2)).unwrap().1;
+    assert!(win > 0.9, "The uniform prior gives the win 1/3, the improved policy gave it {}", win);
+
+    // the policy only needs the root statistics, not a Gumbel search
+    let puct = search_almost_won(RootSelection::PUCT, 10);
+    let win = puct.completed_q_policy(50., 1.).into_iter().find(|(action, _)| *action == (2, 2)).unwrap().1;
+    assert!(win > 0.9, "After a PUCT search the improved policy gave the win {}", win);
+}
+
+#[test]
+fn test_playouts_only_go_to_considered_actions() {
+    let considered = RootSelection::Gumbel { considered_actions: 2, c_visit: 50., c_scale: 1. };
+    let mcts = search_almost_won(considered, 20);
+
+    let initial = MCTSConfig::default().initial_edge_visits;
+    let searched = mcts.root_node().edges.iter().filter(|edge| edge.visits > initial).count();
+    assert!(searched <= 2, "{} root edges were searched", searched);
+    let visits: u32 = mcts.root_node().edges.iter().map(|edge| edge.visits - initial).sum();
+    assert_eq!(visits, 19, "Every playout after the first, which expands the root, goes through a root edge");
+}
+
+#[test]
+fn test_gumbel_action_resets_with_root() {
+    let mut mcts = search_almost_won(GUMBEL, 10);
+    assert!(mcts.gumbel_action().is_some());
+
+    mcts.advance((2, 1));
+    assert_eq!(mcts.gumbel_action(), None, "The choice was for the old root");
+
+    let puct = search_almost_won(RootSelection::PUCT, 10);
+    assert_eq!(puct.gumbel_action(), None, "PUCT doesn't run sequential halving");
+}
+
+#[test]
+#[should_panic(expected = "Gumbel root selection needs a fixed budget")]
+fn test_search_with_limits_rejects_gumbel() {
+    let mut mcts = search_almost_won(GUMBEL, 0);
+    mcts.search_with_limits(&SearchLimits { playouts: Some(10), ..Default::default() });
+}
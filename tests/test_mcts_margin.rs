@@ -0,0 +1,92 @@
+mod common;
+
+use mcts_rs::game::Game;
+use mcts_rs::mcts::{MarginBlend, MCTS, MCTSConfig, MoveSelection, ValueStats};
+use common::{Fixture, FixtureState};
+
+// Player 1 picks one of three endings and the game is over, won or lost by the margins below.
+// The board is the ending picked, if any.
+const MARGINS: [f64; 3] = [1.5, 10., -3.];
+
+type Margins = Fixture<Option<usize>, usize>;
+
+const ENDINGS: Margins = Fixture {
+    describe: |state| match state {
+        Some(ending) => {
+            let margin = MARGINS[ending];
+            let sign = margin.signum() as i32;
+            FixtureState::finished(state, -1, vec![(1, sign), (-1, -sign)]).with_utilities(vec![(1, margin), (-1, -margin)])
+        }
+        None => FixtureState::playing(state, 1, vec![0, 1, 2])
+    },
+    next: |_, action| Some(action)
+};
+
+fn search_margins(config: MCTSConfig) -> MCTS<Margins> {
+    let mut game = ENDINGS;
+    let start = game.get_state(&None);
+    let mut mcts = MCTS::with_config(game, start, config);
+    mcts.search(100);
+    mcts
+}
+
+fn q_of(mcts: &MCTS<Margins>, ending: usize) -> f64 {
+    mcts.root_stats().into_iter().find(|(action, _, _)| *action == ending).unwrap().2
+}
+
+#[test]
+fn test_raw_utilities_are_backed_up() {
+    let mcts = search_margins(MCTSConfig::default());
+
+    assert_eq!(mcts.best_action(MoveSelection::MaxQ), Some(1), "The biggest win should come first");
+    for (ending, margin) in MARGINS.into_iter().enumerate() {
+        assert_eq!(q_of(&mcts, ending), margin, "Ending {} should be worth its margin", ending);
+    }
+    let stats = &mcts.root_node().stats;
+    assert_eq!((stats.min, stats.max), (-3., 10.), "Margins aren't rounded or dropped");
+}
+
+#[test]
+fn test_margin_blend() {
+    let margin_blend = MarginBlend { weight: 0.5, scale: 5. };
+    let mcts = search_margins(MCTSConfig { margin_blend: Some(margin_blend), ..Default::default() });
+
+    assert_eq!(mcts.best_action(MoveSelection::MaxQ), Some(1));
+    for (ending, margin) in MARGINS.into_iter().enumerate() {
+        let expected = margin.signum() * 0.5 + 0.5 * (margin / 5.).tanh();
+        assert!((q_of(&mcts, ending) - expected).abs() < 1e-12, "Ending {} is worth {}, expected {}", ending, q_of(&mcts, ending), expected);
+    }
+    assert!(mcts.root_node().stats.max <= 1., "Blended values stay within [-1, 1]");
+
+    // with no weight on the margin every win is just a win
+    let win_loss = search_margins(MCTSConfig { margin_blend: Some(MarginBlend { weight: 0., scale: 5. }), ..Default::default() });
+    assert_eq!(q_of(&win_loss, 0), 1.);
+    assert_eq!(q_of(&win_loss, 1), 1.);
+    assert_eq!(q_of(&win_loss, 2), -1.);
+}
+
+#[test]
+#[should_panic(expected = "The solver needs utilities of 1, 0 or -1")]
+fn test_solver_rejects_margins() {
+    // proving the 1.5 ending a win would cut off the better 10 ending
+    search_margins(MCTSConfig { solver: true, ..Default::default() });
+}
+
+#[test]
+#[should_panic(expected = "The solver can't prove blended margins")]
+fn test_solver_rejects_margin_blend() {
+    search_margins(MCTSConfig { solver: true, margin_blend: Some(MarginBlend { weight: 0.5, scale: 5. }), ..Default::default() });
+}
+
+#[test]
+fn test_value_stats() {
+    let mut stats = ValueStats::default();
+    for value in [1., 2., 3., 4.] {
+        stats.push(value);
+    }
+
+    assert_eq!(stats.count, 4);
+    assert_eq!(stats.mean, 2.5);
+    assert_eq!(stats.variance(), 1.25);
+    assert_eq!((stats.min, stats.max), (1., 4.));
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use mcts_rs::game::Game;
+use mcts_rs::mcts::{MCTS, MCTSConfig, MoveSelection};
+use common::{Fixture, FixtureState};
+
+// Players 1, 2 and 3 each pick 0 or 1 once, in that order, and the three picks index PAYOFFS.
+// Under max^n player 3 answers every pick, player 2 knows it, and so on: after 0 player 2 goes 1
+// and player 3 goes 0, leaving player 1 nothing, while after 1 player 2 goes 0 and player 3 goes 0,
+// giving player 1 a win. The board is the picks so far.
+const PAYOFFS: [[i32; 3]; 8] = [
+    [1, 0, 0], [0, 0, 1], // 0, 0, _
+    [0, 1, 1], [1, 0, 0], // 0, 1, _
+    [1, 0, 1], [0, 1, 0], // 1, 0, _
+    [0, 0, -1], [0, -1, 0] // 1, 1, _
+];
+
+const THREE_PICKS: Fixture<Vec<u8>, u8> = Fixture {
+    describe: |state| {
+        let player = 1 + (state.len() % 3) as i32;
+        if state.len() < 3 {
+            return FixtureState::playing(state, player, vec![0, 1]);
+        }
+        let leaf = (state[0] * 4 + state[1] * 2 + state[2]) as usize;
+        let result = (1..=3).map(|player| (player, PAYOFFS[leaf][player as usize - 1])).collect();
+        FixtureState::finished(state, player, result)
+    },
+    next: |picks, action| [picks.as_slice(), &[action]].concat()
+};
+
+#[test]
+fn test_multi_player_values_follow_max_n() {
+    let mut game = THREE_PICKS;
+    let start = game.get_state(&vec![]);
+    let config = MCTSConfig { multi_player: true, ..Default::default() };
+    let mut mcts = MCTS::with_config(game, start, config);
+    mcts.search(1000);
+
+    assert_eq!(mcts.best_action(MoveSelection::MaxVisits), Some(1), "Only picking 1 lets player 1 win");
+    let stats = mcts.root_stats();
+    assert!(stats[1].2 > 0.5, "Picking 1 should be worth about a win to player 1, got {}", stats[1].2);
+    assert!(stats[0].2 < 0.5, "Picking 0 should be worth about nothing to player 1, got {}", stats[0].2);
+
+    let root = mcts.root_node();
+    let one = mcts.node(root.edges[1].child);
+    assert!(one.values[&3] > 0.5, "Player 3 wins alongside player 1 after 1, 0, 0, got {}", one.values[&3]);
+    assert_eq!(root.values.len(), 3, "The root keeps a value for every player");
+}
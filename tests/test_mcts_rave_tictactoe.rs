@@ -0,0 +1,79 @@
+use ndarray::prelude::*;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use mcts_rs::evaluator::{Evaluator, RandomRollout, TruncatedRollout};
+use mcts_rs::game::Game;
+use mcts_rs::games::tictactoe::TicTacToe;
+use mcts_rs::mcts::{MCTS, MCTSConfig, MoveSelection, Rave};
+
+const RAVE: Rave = Rave { equivalence: 50. };
+
+fn search(board: Array2<i8>, rave: Option<Rave>, n: u32) -> MCTS<TicTacToe> {
+    let mut tictactoe = TicTacToe::new();
+    let state = tictactoe.get_state(&board);
+    let mut mcts = MCTS::with_config(tictactoe, state, MCTSConfig { rave, ..Default::default() });
+    mcts.search(n);
+    mcts
+}
+
+#[test]
+fn test_rave_beta_decays() {
+    assert_eq!(RAVE.beta(0), 1., "AMAF is all there is before an edge is visited");
+    assert!((RAVE.beta(50) - 0.5).abs() < 1e-12, "Q and AMAF weigh the same at `equivalence` visits");
+    assert!(RAVE.beta(1000) < RAVE.beta(100));
+}
+
+#[test]
+fn test_rollouts_only_record_moves_when_asked() {
+    let mut tictactoe = TicTacToe::new();
+    let empty = tictactoe.get_state(&Array2::zeros((3, 3)));
+    let mut rng = StdRng::seed_from_u64(0);
+
+    let evaluation = RandomRollout.evaluate(&mut tictactoe, empty.clone(), &mut rng);
+    assert!(evaluation.moves.is_empty(), "Plain evaluations don't pay for recording");
+    let evaluation = RandomRollout.evaluate_recording_moves(&mut tictactoe, empty.clone(), &mut rng);
+    assert!(evaluation.moves.len() >= 5, "A game takes at least five moves, got {:?}", evaluation.moves);
+    assert_eq!(evaluation.moves[0].0, 1, "X moves first");
+
+    let mut truncated = TruncatedRollout { max_depth: 2, cutoff: RandomRollout };
+    assert!(truncated.evaluate(&mut tictactoe, empty.clone(), &mut rng).moves.is_empty());
+    let evaluation = truncated.evaluate_recording_moves(&mut tictactoe, empty, &mut rng);
+    assert!(evaluation.moves.len() >= 5, "The cutoff's rollout is recorded too, got {:?}", evaluation.moves);
+}
+
+#[test]
+fn test_amaf_learns_from_every_move_of_a_playout() {
+    let empty = Array2::<i8>::zeros((3, 3));
+    let plain = search(empty.clone(), None, 100);
+    assert!(plain.root_node().edges.iter().all(|edge| edge.amaf_visits == 0), "No AMAF statistics without RAVE");
+
+    let rave = search(empty, Some(RAVE), 100);
+    let root = rave.root_node();
+    assert!(root.edges.iter().all(|edge| edge.amaf_visits > 0), "Every opening was played at some point");
+    let amaf_visits: u32 = root.edges.iter().map(|edge| edge.amaf_visits).sum();
+    let visits: u32 = root.edges.iter().map(|edge| edge.visits).sum();
+    assert!(amaf_visits > 2 * visits, "X plays several moves per playout, got {} AMAF updates for {} visits", amaf_visits, visits);
+    assert!(root.edges.iter().all(|edge| edge.amaf_Q().unwrap().abs() <= 1.));
+}
+
+#[test]
+fn test_rave_picks_winning_move_when_almost_won() {
+    let one_move_to_win = arr2(&[
+        [ 1, -1,  0],
+        [ 1,  1, -1],
+        [-1,  0,  0]]);
+    let mcts = search(one_move_to_win, Some(RAVE), 10);
+
+    assert_eq!(mcts.best_action(MoveSelection::MaxQ), Some((2, 2)), "RAVE did not pick the winning move");
+}
+
+#[test]
+fn test_rave_blocks_win() {
+    let o_can_win = arr2(&[
+        [-1,  1,  0],
+        [ 1, -1,  0],
+        [ 0,  0,  0]]);
+    let mcts = search(o_can_win, Some(RAVE), 200);
+
+    assert_eq!(mcts.best_action(MoveSelection::MaxVisits), Some((2, 2)), "RAVE did not block the diagonal");
+}
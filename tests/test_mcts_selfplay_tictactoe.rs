@@ -0,0 +1,70 @@
+use ndarray::Array2;
+use mcts_rs::mcts::{DirichletNoise, MCTS, MCTSConfig, MoveSelection, TemperatureSchedule};
+use mcts_rs::game::Game;
+use mcts_rs::games::tictactoe::TicTacToe;
+
+const NOISE: DirichletNoise = DirichletNoise { alpha: 0.3, epsilon: 0.25 };
+
+fn search_empty_board(config: MCTSConfig, n: u32) -> MCTS<TicTacToe> {
+    let mut tictactoe = TicTacToe::new();
+    let empty = tictactoe.get_state(&Array2::<i8>::zeros((3, 3)));
+    let mut mcts = MCTS::with_config(tictactoe, empty, config);
+    mcts.search(n);
+    mcts
+}
+
+fn root_priors(mcts: &MCTS<TicTacToe>) -> Vec<f64> {
+    mcts.root_node().edges.iter().map(|edge| edge.prior).collect()
+}
+
+#[test]
+fn test_root_noise_perturbs_priors() {
+    let plain = search_empty_board(MCTSConfig::default(), 1);
+    assert!(root_priors(&plain).iter().all(|&prior| prior == 1.), "No noise by default");
+
+    let noisy = search_empty_board(MCTSConfig { root_noise: Some(NOISE), ..Default::default() }, 1);
+    let priors = root_priors(&noisy);
+    assert!(priors.iter().any(|&prior| prior != 1.), "Noise was mixed in");
+    assert!(priors.iter().all(|&prior| prior >= 1. - NOISE.epsilon), "The prior keeps 1 - epsilon of its weight");
+    let total: f64 = priors.iter().sum();
+    assert!((total - 9.).abs() < 1e-9, "Noise keeps the priors' total, got {}", total);
+
+    let reseeded = search_empty_board(MCTSConfig { root_noise: Some(NOISE), seed: 1, ..Default::default() }, 1);
+    assert_ne!(root_priors(&reseeded), priors, "Different seeds draw different noise");
+}
+
+#[test]
+fn test_new_root_gets_noise() {
+    let mut mcts = search_empty_board(MCTSConfig { root_noise: Some(NOISE), ..Default::default() }, 200);
+    let child = mcts.root_node().edges.iter().find(|edge| edge.action == (1, 1)).unwrap().child;
+    assert!(mcts.node(child).edges.iter().all(|edge| edge.prior == 1.), "Only the root is noised");
+
+    mcts.advance((1, 1));
+    assert!(!mcts.root_node().edges.is_empty(), "The new root was already expanded");
+    assert!(mcts.root_node().edges.iter().any(|edge| edge.prior != 1.), "The reused subtree's root is noised when it becomes the root");
+}
+
+#[test]
+fn test_temperature_schedule() {
+    let schedule = TemperatureSchedule(vec![(2, 1.), (4, 0.5), (6, 0.)]);
+
+    assert_eq!(schedule.temperature(0), 1., "Moves before the first step get 1");
+    assert_eq!(schedule.temperature(3), 1.);
+    assert_eq!(schedule.temperature(4), 0.5);
+    assert_eq!(schedule.temperature(100), 0.);
+}
+
+#[test]
+fn test_sample_action_follows_temperature() {
+    let mut mcts = search_empty_board(MCTSConfig::default(), 500);
+    let schedule = TemperatureSchedule(vec![(0, 1.), (2, 0.)]);
+
+    let greedy = mcts.best_action(MoveSelection::MaxVisits);
+    for _ in 0..20 {
+        assert_eq!(mcts.sample_action(2, &schedule), greedy, "Temperature 0 plays the most visited move");
+    }
+    let mut sampled: Vec<(usize, usize)> = (0..50).filter_map(|_| mcts.sample_action(0, &schedule)).collect();
+    sampled.sort();
+    sampled.dedup();
+    assert!(sampled.len() > 1, "Temperature 1 should play more than one opening, got {:?}", sampled);
+}
@@ -1,7 +1,11 @@
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 use ndarray::prelude::*;
+use rand::rngs::StdRng;
+use mcts_rs::evaluator::{Evaluation, Evaluator};
 use mcts_rs::game::Game;
-use mcts_rs::games::tictactoe::TicTacToe;
-use mcts_rs::mcts::MCTS;
+use mcts_rs::games::tictactoe::{TicTacToe, TicTacToeState};
+use mcts_rs::mcts::{MCTS, MCTSConfig, MoveSelection, SearchLimits};
 
 #[test]
 fn test_mcts_picks_winning_move_when_almost_won() {
@@ -14,29 +18,8 @@ fn test_mcts_picks_winning_move_when_almost_won() {
     let mut mcts = MCTS::new(tictactoe,almost_won);
     mcts.search(10);
 
-    let child_states = {
-        let root = mcts.root.borrow();
-        root.child_to_edge_visits
-            .keys()
-            .cloned() 
-            .collect::<Vec<_>>()
-    };
-
-    let child_nodes = child_states
-        .into_iter()
-        .map(|child_state_rc| mcts.get_node(child_state_rc))
-        .collect::<Vec<_>>();
-
-    let winning_node = child_nodes
-        .into_iter()
-        .max_by(|a, b| {
-            let a_q = a.borrow().Q;
-            let b_q = b.borrow().Q;
-            a_q.partial_cmp(&b_q).unwrap()
-        }).expect("No child found");
-
-    let winning_child_state = &winning_node.borrow().game_state.state;
-    assert_eq!(winning_child_state[[2, 2]], 1, "MCTS did not pick the winning move.");
+    let winning_action = mcts.best_action(MoveSelection::MaxQ).expect("No child found");
+    assert_eq!(winning_action, (2, 2), "MCTS did not pick the winning move.");
 }
 
 #[test]
@@ -51,9 +34,9 @@ fn test_mcts_results_contain_no_losses() {
     let mut mcts = MCTS::new(tictactoe,almost_won);
     mcts.search(50);
 
-    let root = mcts.root.borrow();
-    let losses_for_o = *root.results.get(&-1).expect("results broken");
-    assert_eq!(losses_for_o, 0 ,"Expected zero losses for player -1 at the root");
+    let root = mcts.root_node();
+    assert!(root.stats.count > 0, "Rewards were backed up through the root");
+    assert!(root.stats.min >= 0., "Expected zero losses for the player to move at the root");
 }
 
 #[test]
@@ -65,48 +48,211 @@ fn test_mcts_blocks_win() {
         [ 0,  0,  0],
     ]);
     let o_can_win = tictactoe.get_state(&board);
-    let blocked = arr2(&[
-        [-1,  1,  0],
+    let mut mcts = MCTS::new(tictactoe,o_can_win);
+    mcts.search(50);
+
+    let chosen_action = mcts.best_action(MoveSelection::MaxQ).expect("No child found");
+    assert_eq!(chosen_action, (2, 2), "MCTS did not block the winning move");
+}
+
+#[test]
+fn test_one_run_expands_and_selects_one() {
+    let mut tictactoe = TicTacToe::new();
+    let empty_board = Array2::zeros((3, 3));
+    let new_game = tictactoe.get_state(&empty_board);
+    let mut mcts = MCTS::new(tictactoe, new_game);
+    mcts.run();
+    assert_eq!(mcts.root_node().N, 10, "One run visits the root and all it's children. 1 + 9 = 10 = root.N");
+    mcts.run();
+    assert_eq!(mcts.root_node().N, 11, "one more run has only one path up to root so + 1 more");
+}
+
+#[test]
+fn test_policy_is_a_distribution_over_root_actions() {
+    let mut tictactoe = TicTacToe::new();
+    let one_move_to_win = arr2(&[
         [ 1, -1,  0],
-        [ 0,  0,  1],
+        [ 1,  1, -1],
+        [-1,  0,  0],
     ]);
-    let mut mcts = MCTS::new(tictactoe,o_can_win);
+    let almost_won = tictactoe.get_state(&one_move_to_win);
+    let mut mcts = MCTS::new(tictactoe,almost_won);
     mcts.search(50);
 
-    let child_states = {
-        let root = mcts.root.borrow();
-        root.child_to_edge_visits
-            .keys()
-            .cloned()
-            .collect::<Vec<_>>()
+    let policy = mcts.policy(1.);
+    assert_eq!(policy.len(), 3, "One entry per legal action at the root");
+    let total: f64 = policy.iter().map(|(_, p)| p).sum();
+    assert!((total - 1.).abs() < 1e-9, "Policy should sum to 1, got {}", total);
+
+    let greedy = mcts.policy(0.);
+    let best = mcts.best_action(MoveSelection::MaxVisits).unwrap();
+    for (action, p) in greedy {
+        let expected = if action == best { 1. } else { 0. };
+        assert_eq!(p, expected, "Temperature 0 should put all mass on the most visited action");
+    }
+}
+
+#[test]
+fn test_advance_keeps_subgraph_and_drops_the_rest() {
+    let mut tictactoe = TicTacToe::new();
+    let empty_board = Array2::zeros((3, 3));
+    let new_game = tictactoe.get_state(&empty_board);
+    let mut mcts = MCTS::new(tictactoe, new_game);
+    mcts.search(200);
+
+    let action = mcts.best_action(MoveSelection::MaxVisits).unwrap();
+    let nodes_before = mcts.nodes.len();
+    let child_visits = {
+        let root_state = mcts.root_node().game_state.clone();
+        let child_state = mcts.game.transition(root_state, action);
+        let child = mcts.get_node(child_state);
+        mcts.node(child).N
     };
 
-    let child_nodes = child_states
-        .into_iter()
-        .map(|child_state_rc| mcts.get_node(child_state_rc.clone()))
-        .collect::<Vec<_>>();
-
-    let chosen_node = child_nodes
-        .into_iter()
-        .max_by(|a, b| {
-            let a_q = a.borrow().Q;
-            let b_q = b.borrow().Q;
-            a_q.partial_cmp(&b_q).unwrap()
-        })
-        .expect("No child found");
-
-    let chosen_state = &chosen_node.borrow().game_state.state;
-    assert_eq!(*chosen_state, blocked, "MCTS did not block the winning move");
+    mcts.advance(action);
+    assert_eq!(mcts.root_node().N, child_visits, "The new root keeps the statistics it had as a child");
+    assert_eq!(mcts.root_node().game_state.state[action], 1, "The new root is the child reached by the action");
+    assert!(mcts.nodes.len() < nodes_before, "Unreachable nodes should be dropped");
+    assert_eq!(mcts.node_ids.len(), mcts.nodes.len(), "The transposition table only holds kept nodes");
+    for node in &mcts.nodes {
+        assert!(node.edges.iter().all(|edge| edge.child.0 < mcts.nodes.len()), "Edges are renumbered with the arena");
+    }
+
+    // play the game out, reusing the graph every move
+    while !mcts.root_node().game_state.is_terminal {
+        mcts.search(50);
+        let action = mcts.best_action(MoveSelection::MaxVisits).unwrap();
+        mcts.advance(action);
+    }
+    assert_eq!(mcts.nodes.len(), 1, "Only the terminal root is reachable at the end of the game");
 }
 
 #[test]
-fn test_one_run_expands_and_selects_one() {
+fn test_search_with_limits_stops_at_first_limit() {
     let mut tictactoe = TicTacToe::new();
     let empty_board = Array2::zeros((3, 3));
     let new_game = tictactoe.get_state(&empty_board);
     let mut mcts = MCTS::new(tictactoe, new_game);
+
+    let limits = SearchLimits { playouts: Some(1_000_000), max_nodes: Some(500), ..Default::default() };
+    mcts.search_with_limits(&limits);
+    assert!(mcts.nodes.len() >= 500 && mcts.nodes.len() < 510, "Node limit overshoots by at most one expansion");
+
+    let limits = SearchLimits { playouts: Some(100), ..Default::default() };
+    assert_eq!(mcts.search_with_limits(&limits), 100, "Playout limit should be exact");
+
+    let max_memory = mcts.memory_estimate() * 2;
+    let limits = SearchLimits { max_memory: Some(max_memory), ..Default::default() };
+    mcts.search_with_limits(&limits);
+    assert!(mcts.memory_estimate() >= max_memory, "Memory limit should be reached before stopping");
+
+    let start = Instant::now();
+    let limits = SearchLimits { time: Some(Duration::from_millis(50)), ..Default::default() };
+    assert!(mcts.search_with_limits(&limits) > 0);
+    assert!(start.elapsed() < Duration::from_secs(1), "Time limit should stop the search");
+}
+
+#[test]
+fn test_search_with_limits_needs_a_bound() {
+    let mut tictactoe = TicTacToe::new();
+    let empty_board = Array2::zeros((3, 3));
+    let new_game = tictactoe.get_state(&empty_board);
+    let mut mcts = MCTS::new(tictactoe, new_game);
+
+    assert_eq!(mcts.search_with_limits(&SearchLimits::default()), 0, "No limit means no playouts, not a search that never ends");
+    let limits = SearchLimits { early_stop: true, ..Default::default() };
+    assert_eq!(mcts.search_with_limits(&limits), 0, "Early stopping alone isn't a bound");
+    assert_eq!(mcts.root_node().N, 0);
+}
+
+#[test]
+fn test_search_with_limits_stops_early_when_decided() {
+    let mut tictactoe = TicTacToe::new();
+    let one_move_to_win = arr2(&[
+        [ 1, -1,  0],
+        [ 1,  1, -1],
+        [-1,  0,  0],
+    ]);
+    let almost_won = tictactoe.get_state(&one_move_to_win);
+    let mut mcts = MCTS::new(tictactoe, almost_won);
+
+    let limits = SearchLimits { playouts: Some(1000), early_stop: true, ..Default::default() };
+    let playouts = mcts.search_with_limits(&limits);
+    assert!(playouts < 1000, "Search should stop once the winning move can't be overtaken");
+    assert_eq!(mcts.best_action(MoveSelection::MaxVisits), Some((2, 2)));
+}
+
+#[test]
+fn test_lazy_expansion_config() {
+    let mut tictactoe = TicTacToe::new();
+    let empty_board = Array2::zeros((3, 3));
+    let new_game = tictactoe.get_state(&empty_board);
+    let config = MCTSConfig { rollout_new_children: false, ..Default::default() };
+    let mut mcts = MCTS::with_config(tictactoe, new_game, config);
     mcts.run();
-    assert_eq!(mcts.root.borrow().N, 10, "One run visits the root and all it's children. 1 + 9 = 10 = root.N");
+    assert_eq!(mcts.root_node().N, 2, "Without expansion rollouts one run only visits the root and one child");
+    let visits: u32 = mcts.root_node().edges.iter().map(|edge| edge.visits).sum();
+    assert_eq!(visits, 1, "Initial edge visits only apply to children rolled out during expansion");
     mcts.run();
-    assert_eq!(mcts.root.borrow().N, 11, "one more run has only one path up to root so + 1 more");
-}
\ No newline at end of file
+    assert_eq!(mcts.root_node().N, 3, "one more run has only one path up to root so + 1 more");
+}
+
+#[test]
+fn test_secure_child_skips_unvisited_edges() {
+    let mut tictactoe = TicTacToe::new();
+    let empty_board = Array2::zeros((3, 3));
+    let new_game = tictactoe.get_state(&empty_board);
+    let config = MCTSConfig { rollout_new_children: false, ..Default::default() };
+    let mut mcts = MCTS::with_config(tictactoe, new_game, config);
+    mcts.run();
+
+    let visited = mcts.root_node().edges.iter().find(|edge| edge.visits > 0).unwrap().action;
+    assert_eq!(mcts.best_action(MoveSelection::Secure(0.)), Some(visited), "Only the visited edge has a lower bound");
+    assert_eq!(mcts.best_action(MoveSelection::Secure(1.)), Some(visited));
+}
+
+#[test]
+fn test_same_seed_gives_same_search() {
+    let search = |seed| {
+        let mut tictactoe = TicTacToe::new();
+        let empty_board = Array2::zeros((3, 3));
+        let new_game = tictactoe.get_state(&empty_board);
+        let config = MCTSConfig { seed, c_puct: 2., ..Default::default() };
+        let mut mcts = MCTS::with_config(tictactoe, new_game, config);
+        mcts.search(100);
+        mcts.policy(1.)
+    };
+    assert_eq!(search(7), search(7), "Search should be reproducible from the seed");
+}
+
+#[test]
+fn test_evaluator_prior_is_stored_on_edges_and_steers_search() {
+    // values every state as even, but only wants to play in the top left corner
+    struct CornerPrior;
+    impl Evaluator<TicTacToe> for CornerPrior {
+        fn evaluate(&mut self, _game: &mut TicTacToe, state: Rc<TicTacToeState>, _rng: &mut StdRng) -> Evaluation<(usize, usize)> {
+            if state.is_terminal {
+                return Evaluation::terminal(&*state);
+            }
+            let prior = state.all_legal_actions.clone().unwrap()
+                .into_iter()
+                .map(|action| (action, if action == (0, 0) { 3. } else { 1. }))
+                .collect();
+            Evaluation::new([(1, 0.), (-1, 0.)].into_iter().collect(), Some(prior))
+        }
+    }
+
+    let mut tictactoe = TicTacToe::new();
+    let empty_board = Array2::zeros((3, 3));
+    let new_game = tictactoe.get_state(&empty_board);
+    let config = MCTSConfig { initial_edge_visits: 0, rollout_new_children: false, ..Default::default() };
+    let mut mcts = MCTS::with_evaluator(tictactoe, new_game, CornerPrior, config);
+    mcts.search(20);
+
+    let root = mcts.root_node();
+    let total: f64 = root.edges.iter().map(|edge| edge.prior).sum();
+    assert!((total - 1.).abs() < 1e-9, "Priors should be normalized over the legal actions");
+    let corner = root.edges.iter().find(|edge| edge.action == (0, 0)).unwrap();
+    assert_eq!(corner.prior, 3. / 11.);
+    assert_eq!(mcts.best_action(MoveSelection::MaxVisits), Some((0, 0)), "The prior should steer visits to the corner");
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use ndarray::prelude::*;
+use mcts_rs::game::Game;
+use mcts_rs::mcts::{MCTS, MoveSelection};
+use common::{Fixture, FixtureState};
+
+// One move game on a 1x1 board: (0,0) and (0,1) both write a 1 and win, (0,2) writes a -1 and loses.
+type OneMove = Fixture<Array2<i8>, (usize, usize)>;
+
+const ONE_MOVE: OneMove = Fixture {
+    describe: |state| match state[[0, 0]] {
+        1 => FixtureState::finished(state, -1, vec![(1, 1), (-1, -1)]),
+        -1 => FixtureState::finished(state, -1, vec![(1, -1), (-1, 1)]),
+        _ => FixtureState::playing(state, 1, vec![(0, 0), (0, 1), (0, 2)])
+    },
+    next: |_, action| arr2(&[[if action == (0, 2) { -1 } else { 1 }]])
+};
+
+#[test]
+fn test_transposing_actions_keep_separate_edges() {
+    let mut game = ONE_MOVE;
+    let start = game.get_state(&arr2(&[[0]]));
+    let mut mcts = MCTS::new(game, start);
+    mcts.search(20);
+
+    let root = mcts.root_node();
+    let actions: Vec<_> = root.edges.iter().map(|edge| edge.action).collect();
+    assert_eq!(actions, vec![(0, 0), (0, 1), (0, 2)], "One edge per action, labeled with its action");
+    assert_eq!(root.edges[0].child, root.edges[1].child, "Both winning actions reach the same child");
+    assert_eq!(mcts.nodes.len(), 3, "The shared child is stored once");
+
+    let best = mcts.best_action(MoveSelection::MaxQ).unwrap();
+    assert_ne!(best, (0, 2), "MCTS picked the losing action");
+    assert_eq!(mcts.policy(1.).len(), 3);
+}
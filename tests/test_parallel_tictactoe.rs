@@ -0,0 +1,72 @@
+use ndarray::prelude::*;
+use mcts_rs::evaluator::RandomRollout;
+use mcts_rs::games::tictactoe::TicTacToe;
+use mcts_rs::mcts::{MCTSConfig, MoveSelection, SearchLimits};
+use mcts_rs::parallel::{ParallelConfig, ParallelMCTS, root_parallel_search};
+
+#[test]
+fn test_parallel_mcts_picks_winning_move_when_almost_won() {
+    let one_move_to_win = arr2(&[
+        [ 1, -1,  0],
+        [ 1,  1, -1],
+        [-1,  0,  0]]);
+    let parallel = ParallelConfig { threads: 4, virtual_loss: 1 };
+    let mcts = ParallelMCTS::new(TicTacToe::new, one_move_to_win, RandomRollout, MCTSConfig::default(), parallel);
+    mcts.search(100);
+
+    let winning_action = mcts.best_action(MoveSelection::MaxQ).expect("No child found");
+    assert_eq!(winning_action, (2, 2), "MCTS did not pick the winning move.");
+}
+
+#[test]
+fn test_parallel_mcts_blocks_win() {
+    let board = arr2(&[
+        [-1,  1,  0],
+        [ 1, -1,  0],
+        [ 0,  0,  0],
+    ]);
+    let parallel = ParallelConfig { threads: 4, virtual_loss: 3 };
+    let mcts = ParallelMCTS::new(TicTacToe::new, board, RandomRollout, MCTSConfig::default(), parallel);
+    mcts.search(400);
+
+    let chosen_action = mcts.best_action(MoveSelection::MaxVisits).expect("No child found");
+    assert_eq!(chosen_action, (2, 2), "MCTS did not block the winning move");
+}
+
+#[test]
+fn test_parallel_search_releases_virtual_losses() {
+    let parallel = ParallelConfig { threads: 8, virtual_loss: 1 };
+    let mcts = ParallelMCTS::new(TicTacToe::new, Array2::zeros((3, 3)), RandomRollout, MCTSConfig::default(), parallel);
+    mcts.search(500);
+    mcts.search(500);
+
+    assert!(mcts.root.lock().unwrap().N > 1000, "Every playout passes through the root");
+    for node in mcts.nodes.read().unwrap().values() {
+        assert_eq!(node.lock().unwrap().virtual_losses, 0, "No playout is in flight after search returns");
+    }
+    let total: f64 = mcts.policy(1.).iter().map(|(_, p)| p).sum();
+    assert!((total - 1.).abs() < 1e-9);
+}
+
+#[test]
+fn test_root_parallel_merges_worker_statistics() {
+    let one_move_to_win = arr2(&[
+        [ 1, -1,  0],
+        [ 1,  1, -1],
+        [-1,  0,  0]]);
+    let limits = SearchLimits { playouts: Some(50), ..Default::default() };
+    let stats = root_parallel_search(TicTacToe::new, one_move_to_win, RandomRollout, MCTSConfig::default(), 4, &limits);
+
+    assert_eq!(stats.workers.len(), 4);
+    assert_eq!(stats.merged.len(), 3, "One merged entry per legal action at the root");
+    for merged in &stats.merged {
+        let worker_visits: u32 = stats.workers.iter()
+            .flatten()
+            .filter(|(action, _, _)| *action == merged.action)
+            .map(|&(_, visits, _)| visits)
+            .sum();
+        assert_eq!(merged.visits, worker_visits, "Merged visits are the sum over workers");
+    }
+    assert_eq!(stats.best_action(MoveSelection::MaxQ), Some((2, 2)), "MCTS did not pick the winning move.");
+    assert_eq!(stats.best_action(MoveSelection::MaxVisits), Some((2, 2)), "MCTS did not pick the winning move.");
+}
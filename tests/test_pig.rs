@@ -0,0 +1,38 @@
+use mcts_rs::games::pig::{Pig, PigAction, PigBoard};
+use mcts_rs::game::Game;
+
+fn board(scores: [i32; 2], turn_total: i32) -> PigBoard {
+    PigBoard { scores, turn_total, player: 1, rolling: false }
+}
+
+#[test]
+fn test_pig_all_legal_actions() {
+    let mut pig = Pig::new(10);
+
+    let turn_start = pig.get_state(&board([0, 0], 0));
+    assert_eq!(turn_start.all_legal_actions.clone().unwrap(), vec![PigAction::Roll], "Nothing to hold yet");
+    let mid_turn = pig.get_state(&board([0, 0], 3));
+    let rolled = pig.transition(mid_turn, PigAction::Roll);
+    assert_eq!(rolled.all_legal_actions.clone().unwrap(), vec![], "The die decides");
+    let outcomes = rolled.chance_outcomes.clone().unwrap();
+    assert_eq!(outcomes.len(), 6);
+    assert!(outcomes.iter().all(|&(_, probability)| probability == 1. / 6.));
+}
+
+#[test]
+fn test_pig_rolls_and_holds() {
+    let mut pig = Pig::new(10);
+    let rolling = pig.get_state(&PigBoard { rolling: true, ..board([0, 5], 3) });
+
+    let four = pig.transition(rolling.clone(), PigAction::Face(4));
+    assert_eq!(four.state, board([0, 5], 7), "The roll adds to the turn total");
+    let one = pig.transition(rolling, PigAction::Face(1));
+    assert_eq!(one.state, PigBoard { player: -1, ..board([0, 5], 0) }, "A 1 loses the turn total");
+
+    let held = pig.transition(four, PigAction::Hold);
+    assert_eq!(held.state, PigBoard { player: -1, ..board([7, 5], 0) }, "Holding banks the turn total");
+    assert!(!held.is_terminal);
+    let almost_won = pig.get_state(&board([8, 5], 2));
+    let won = pig.transition(almost_won, PigAction::Hold);
+    assert_eq!(won.result, Some(vec![(1, 1), (-1, -1)]), "Reaching the target wins");
+}
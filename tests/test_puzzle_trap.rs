@@ -0,0 +1,66 @@
+mod common;
+
+use mcts_rs::game::{Game, GameState};
+use mcts_rs::puzzle::PuzzleMCTS;
+use common::{Fixture, FixtureState};
+
+// A deceptive puzzle: set DEPTH bits one at a time, scoring a point per 1, except that setting the
+// first bit is a trap that scores TRAP whatever follows. A random line through the trap beats a
+// random line around it, 6 to 4.5 on average, but the best line leaves the first bit clear and
+// sets all the others. The board is (bits set so far, how many have been chosen).
+const DEPTH: u8 = 10;
+const TRAP: i32 = 6;
+const TREE_SIZE: usize = (1 << (DEPTH + 1)) - 1;
+const PLAYOUTS: u32 = 300;
+
+type Trap = Fixture<(u16, u8), u8>;
+
+const PUZZLE: Trap = Fixture {
+    describe: |state| {
+        let (bits, depth) = state;
+        if depth < DEPTH {
+            return FixtureState::playing(state, 1, vec![0, 1]);
+        }
+        let score = if bits & 1 == 1 { TRAP } else { bits.count_ones() as i32 };
+        FixtureState::finished(state, 1, vec![(1, score)])
+    },
+    next: |&(bits, depth), action| (bits | ((action as u16) << depth), depth + 1)
+};
+
+fn search_trap(n: u32) -> PuzzleMCTS<Trap> {
+    let mut game = PUZZLE;
+    let start = game.get_state(&(0, 0));
+    let mut mcts = PuzzleMCTS::new(game, start);
+    mcts.search(n);
+    mcts
+}
+
+#[test]
+fn test_finds_the_best_line_past_the_trap() {
+    let mcts = search_trap(PLAYOUTS);
+    assert!(mcts.nodes.len() < TREE_SIZE / 4, "{} nodes is too close to the whole tree of {}", mcts.nodes.len(), TREE_SIZE);
+
+    let best_line: Vec<u8> = [0].into_iter().chain([1; DEPTH as usize - 1]).collect();
+    assert_eq!(mcts.best_sequence, best_line);
+    assert_eq!(mcts.best_score, Some(DEPTH as f64 - 1.), "Scores aren't capped at 1");
+    assert_eq!(mcts.root_node().max_score, DEPTH as f64 - 1., "The best score is backed up to the root");
+    assert_eq!(mcts.root_node().visits, PLAYOUTS, "Every playout goes through the root");
+
+    let child = |action: u8| mcts.root_node().children.iter().map(|&id| mcts.node(id)).find(|node| node.action == Some(action)).unwrap();
+    assert_eq!(child(1).max_score, TRAP as f64, "Nothing beats the trap's own score behind it");
+    assert!(child(0).visits > child(1).visits, "The search should leave the trap for the better line, {} to {} visits", child(0).visits, child(1).visits);
+}
+
+#[test]
+fn test_best_sequence_replays_to_best_score() {
+    // too few playouts to find the best line, but whatever was found has to be a real line
+    let mut mcts = search_trap(5);
+
+    assert_eq!(mcts.best_sequence.len(), DEPTH as usize, "The sequence runs to the end of the puzzle");
+    let mut state = mcts.root_node().game_state.clone();
+    for &action in mcts.best_sequence.clone().iter() {
+        state = mcts.game.transition(state, action);
+    }
+    assert!(*state.is_terminal());
+    assert_eq!(state.result().as_ref().unwrap()[0].1 as f64, mcts.best_score.unwrap());
+}
@@ -0,0 +1,60 @@
+use mcts_rs::game::Game;
+use mcts_rs::games::goofspiel::{Goofspiel, GoofspielBoard};
+use mcts_rs::mcts::MoveSelection;
+use mcts_rs::simultaneous::{SimultaneousMCTS, SimultaneousSelection};
+
+const SELECTIONS: [SimultaneousSelection; 2] = [
+    SimultaneousSelection::DecoupledUCT,
+    SimultaneousSelection::RegretMatching { exploration: 0.2 }
+];
+
+fn search_goofspiel(cards: u8, selection: SimultaneousSelection, n: u32) -> SimultaneousMCTS<Goofspiel> {
+    let mut game = Goofspiel;
+    let start = game.get_state(&GoofspielBoard::new(cards));
+    let mut mcts = SimultaneousMCTS::new(game, start, selection);
+    mcts.search(n);
+    mcts
+}
+
+#[test]
+fn test_both_players_bid_high_for_the_big_prize() {
+    // with two cards, bidding 2 on the prize of 2 wins unless the other does the same, which draws
+    for selection in SELECTIONS {
+        let mcts = search_goofspiel(2, selection, 1000);
+
+        assert_eq!(mcts.best_action(1, MoveSelection::MaxVisits), Some(2), "{:?} missed player 1's dominant bid", selection);
+        assert_eq!(mcts.best_action(-1, MoveSelection::MaxVisits), Some(2), "{:?} missed player -1's dominant bid", selection);
+        let low = mcts.root_stats(1).into_iter().find(|(bid, _, _)| *bid == 1).unwrap();
+        assert!(low.2 <= 0., "Bidding 1 can only lose or draw, got {}", low.2);
+    }
+}
+
+#[test]
+fn test_regret_matching_policy_is_a_distribution() {
+    let mcts = search_goofspiel(3, SimultaneousSelection::RegretMatching { exploration: 0.2 }, 3000);
+
+    for player in [1, -1] {
+        let policy = mcts.policy(player);
+        assert_eq!(policy.len(), 3, "One entry per card in hand");
+        let total: f64 = policy.iter().map(|(_, probability)| probability).sum();
+        assert!((total - 1.).abs() < 1e-9, "Policy sums to {}", total);
+    }
+    assert!(mcts.policy(0).is_empty(), "Player 0 isn't playing");
+}
+
+#[test]
+fn test_symmetric_game_is_worth_about_nothing() {
+    for selection in SELECTIONS {
+        let mcts = search_goofspiel(3, selection, 3000);
+
+        let root = mcts.root_node();
+        assert_eq!(root.N, 3000, "Every playout goes through the root");
+        assert_eq!(root.players.len(), 2, "Both players choose at the root");
+        let visits: u32 = root.players[0].visits.iter().sum();
+        assert_eq!(visits, root.players[1].visits.iter().sum::<u32>(), "Every playout is a joint action");
+        for player in root.players.iter() {
+            let value = (0..player.actions.len()).map(|index| player.visits[index] as f64 * player.Q(index)).sum::<f64>() / visits as f64;
+            assert!(value.abs() < 0.3, "{:?} gave player {} a value of {}", selection, player.player, value);
+        }
+    }
+}
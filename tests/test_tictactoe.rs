@@ -1,7 +1,7 @@
 use std::rc::Rc;
 use ndarray::prelude::*;
 use mcts_rs::games::tictactoe::{TicTacToeState, TicTacToe};
-use mcts_rs::game::Game;
+use mcts_rs::game::{Game, ToArray2};
 
 #[test]
 fn test_tictactoe_finds_all_states() {
@@ -26,3 +26,20 @@ fn test_tictactoe_finds_all_states() {
     assert_eq!(tictactoe.game_states.len(), 5478, "The number of states should be 5478");
 }
 
+
+#[test]
+fn test_tictactoe_array2_view() {
+    let mut tictactoe = TicTacToe::new();
+    let empty = tictactoe.get_state(&Array2::zeros((3, 3)));
+    let state = tictactoe.transition(empty, (1, 1));
+    let state = tictactoe.transition(state, (0, 2));
+
+    let grid = state.to_array2();
+    assert_eq!(grid, arr2(&[[0, 0, -1], [0, 1, 0], [0, 0, 0]]));
+    assert_eq!(state.to_string(), grid.to_string(), "States display as their grid");
+
+    let planes = state.to_planes(-1);
+    assert_eq!(planes.dim(), (2, 3, 3));
+    assert_eq!(planes.index_axis(Axis(0), 0), arr2(&[[0., 0., 1.], [0., 0., 0.], [0., 0., 0.]]), "O's own piece comes first");
+    assert_eq!(planes.index_axis(Axis(0), 1), arr2(&[[0., 0., 0.], [0., 1., 0.], [0., 0., 0.]]));
+}